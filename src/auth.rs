@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use subtle::ConstantTimeEq;
+
+use crate::state::AppState;
+
+/// Middleware для маршрутов с событиями: при заданном --api-key требует
+/// `Authorization: Bearer <key>` или `X-API-Key: <key>`
+pub async fn require_api_key(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(expected) = state.config.api_key.as_deref() else {
+        return next.run(request).await;
+    };
+    match presented_key(request.headers()) {
+        // Сравнение за постоянное время, чтобы ключ нельзя было подобрать по задержке ответа
+        Some(key) if bool::from(key.as_bytes().ct_eq(expected.as_bytes())) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Missing or invalid API key\n",
+        )
+            .into_response(),
+    }
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| headers.get("X-API-Key").and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+}
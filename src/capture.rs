@@ -0,0 +1,83 @@
+//! --capture: запись сырых GRPC обновлений в файл для `--replay` (тот же формат, см. replay.rs).
+//! Запись идет в отдельной задаче, цикл подписки только кладет байты в очередь.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use log::{info, warn};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use yellowstone_grpc_proto::prelude::SubscribeUpdate;
+use yellowstone_grpc_proto::prost::Message;
+
+use crate::config::Config;
+
+/// Сколько закодированных обновлений ждет записи, дальше обновления отбрасываются
+const CAPTURE_QUEUE: usize = 10_000;
+
+/// Передается в GRPC подписку; при drop задача записи дописывает очередь и завершается
+pub struct Capture {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl Capture {
+    /// Никогда не ждет: если диск не успевает, обновление теряется с предупреждением
+    pub fn record(&self, update: &SubscribeUpdate) {
+        let mut record = Vec::with_capacity(4 + update.encoded_len());
+        record.extend_from_slice(&(update.encoded_len() as u32).to_be_bytes());
+        update.encode_raw(&mut record);
+        match self.tx.try_send(record) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Capture не успевает, обновление пропущено"),
+            Err(TrySendError::Closed(_)) => {}
+        }
+    }
+}
+
+/// Создаем файл на старте (ошибка пути видна сразу). Задачу нужно дождаться при остановке,
+/// иначе хвост очереди не попадет в файл
+pub async fn spawn(config: &Config) -> anyhow::Result<Option<(Capture, JoinHandle<()>)>> {
+    let Some(path) = &config.capture else {
+        return Ok(None);
+    };
+    let file = File::create(path)
+        .await
+        .with_context(|| format!("Failed to create capture file {}", path.display()))?;
+    info!("🎞️ Запись GRPC обновлений в {}", path.display());
+    let (tx, rx) = mpsc::channel(CAPTURE_QUEUE);
+    let flush_period = Duration::from_millis(config.output_flush_ms);
+    let task = tokio::spawn(run(path.clone(), BufWriter::new(file), rx, flush_period));
+    Ok(Some((Capture { tx }, task)))
+}
+
+async fn run(path: PathBuf, mut writer: BufWriter<File>, mut rx: mpsc::Receiver<Vec<u8>>, period: Duration) {
+    let mut flush = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let mut written = 0u64;
+    loop {
+        let record = tokio::select! {
+            record = rx.recv() => record,
+            _ = flush.tick() => {
+                if let Err(e) = writer.flush().await {
+                    warn!("❌ Не удалось сбросить {}: {}", path.display(), e);
+                }
+                continue;
+            }
+        };
+        let Some(record) = record else {
+            break;
+        };
+        if let Err(e) = writer.write_all(&record).await {
+            warn!("❌ Ошибка записи в {}: {}", path.display(), e);
+            continue;
+        }
+        written += 1;
+    }
+    if let Err(e) = writer.flush().await {
+        warn!("❌ Не удалось сбросить {}: {}", path.display(), e);
+    }
+    info!("🎞️ Capture {}: записано {} обновлений", path.display(), written);
+}
@@ -0,0 +1,50 @@
+//! gzip для SSE. `CompressionLayer` из tower-http буферизует поток до заполнения блока
+//! и поэтому SSE не сжимает, а задержка событий здесь важнее трафика. Здесь каждый кадр
+//! сжимается с sync flush: клиент получает его сразу, а словарь общий на весь поток.
+
+use std::io::Write;
+
+use axum::http::{header, HeaderMap};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::{Stream, StreamExt};
+
+/// Клиент прислал Accept-Encoding с gzip (и не запретил его через q=0)
+pub fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let disabled = parts.any(|param| param == "q=0" || param == "q=0.0");
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !disabled
+        })
+}
+
+/// Сжимает поток SSE кадров; по окончании потока дописывает gzip trailer
+pub fn gzip_frames<S>(frames: S) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send
+where
+    S: Stream<Item = std::io::Result<String>> + Send + 'static,
+{
+    let encoder = GzEncoder::new(Vec::new(), Compression::default());
+    futures::stream::unfold(
+        (frames.boxed(), Some(encoder)),
+        |(mut frames, encoder)| async move {
+            let mut encoder = encoder?;
+            match frames.next().await {
+                Some(Ok(frame)) => {
+                    let chunk = encoder
+                        .write_all(frame.as_bytes())
+                        .and_then(|_| encoder.flush())
+                        .map(|_| std::mem::take(encoder.get_mut()));
+                    Some((chunk, (frames, Some(encoder))))
+                }
+                Some(Err(e)) => Some((Err(e), (frames, Some(encoder)))),
+                None => Some((encoder.finish(), (frames, None))),
+            }
+        },
+    )
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::config::{decode_pubkey, Config};
+
+/// Серверный список создателей из файла: denylist отсекает перечисленных, allowlist пропускает
+/// только их. Формат: адрес base58 на строку, пустые строки и `#` комментарии пропускаются
+#[derive(Debug)]
+pub struct CreatorList {
+    path: PathBuf,
+    allow: bool,
+    creators: HashSet<String>,
+}
+
+impl CreatorList {
+    /// None - ни один список не задан
+    pub fn load(config: &Config) -> Result<Option<Self>> {
+        let (path, allow) = match (&config.creator_allowlist, &config.creator_denylist) {
+            (Some(path), _) => (path, true),
+            (None, Some(path)) => (path, false),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self {
+            path: path.clone(),
+            allow,
+            creators: read_creators(path)?,
+        }))
+    }
+
+    pub fn allows(&self, creator: &str) -> bool {
+        self.creators.contains(creator) == self.allow
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &'static str {
+        if self.allow {
+            "allowlist"
+        } else {
+            "denylist"
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.creators.len()
+    }
+}
+
+fn read_creators(path: &Path) -> Result<HashSet<String>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read creator list {}", path.display()))?;
+    let mut creators = HashSet::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        decode_pubkey(line).with_context(|| format!("{}:{}", path.display(), number + 1))?;
+        creators.insert(line.to_string());
+    }
+    Ok(creators)
+}
@@ -0,0 +1,37 @@
+use std::collections::{HashSet, VecDeque};
+
+/// Множество последних ключей с ограниченной памятью: при переполнении вытесняется самый старый
+#[derive(Debug)]
+pub struct RecentSet {
+    capacity: usize,
+    order: VecDeque<String>,
+    keys: HashSet<String>,
+}
+
+impl RecentSet {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            keys: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Возвращает false, если ключ уже был в окне
+    pub fn insert(&mut self, key: &str) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.keys.contains(key) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.to_string());
+        self.keys.insert(key.to_string());
+        true
+    }
+}
@@ -0,0 +1,81 @@
+//! ?fields= на /events: событие сокращается до перечисленных полей еще до сериализации кадра
+
+use serde_json::Value;
+
+use crate::json::camel_case;
+use crate::{CreateTransaction, EventType};
+
+/// Поля, которые сериализуются только когда заданы (skip_serializing_if), и потому не видны
+/// в `CreateTransaction::default()`
+const OPTIONAL_FIELDS: &[&str] = &["commitment", "account_keys", "account", "upstream"];
+
+/// Выбранные поля; пустой набор означает все поля
+#[derive(Debug, Clone, Default)]
+pub struct FieldSet {
+    fields: Vec<String>,
+}
+
+impl FieldSet {
+    /// Имена через запятую, в snake_case или camelCase. Для полей *_address можно без
+    /// окончания: `?fields=mint,creator,slot`. Err - текст ответа 400 со списком допустимых полей
+    pub fn from_query(query: Option<&str>) -> Result<Self, String> {
+        let mut set = Self::default();
+        let Some(query) = query else {
+            return Ok(set);
+        };
+        let known = known_fields();
+        let requested = form_urlencoded::parse(query.as_bytes())
+            .filter(|(key, _)| key == "fields")
+            .flat_map(|(_, value)| value.split(',').map(|name| name.trim().to_string()).collect::<Vec<_>>());
+        for name in requested.filter(|name| !name.is_empty()) {
+            let Some(field) = known.iter().find(|field| names_field(field, &name)) else {
+                return Err(format!("Unknown field {:?} in ?fields=, valid fields: {}\n", name, known.join(", ")));
+            };
+            if !set.fields.contains(field) {
+                set.fields.push(field.clone());
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// JSON события в snake_case с одними выбранными полями. upstream_reconnect не сокращается:
+    /// выбор полей касается токенов, а без upstream событие теряет смысл
+    pub fn project(&self, create_tx: &CreateTransaction) -> serde_json::Result<Value> {
+        let value = serde_json::to_value(create_tx)?;
+        if self.is_all() || create_tx.event_type == EventType::UpstreamReconnect {
+            return Ok(value);
+        }
+        Ok(match value {
+            Value::Object(mut object) => Value::Object(
+                self.fields
+                    .iter()
+                    .filter_map(|field| object.remove(field).map(|value| (field.clone(), value)))
+                    .collect(),
+            ),
+            other => other,
+        })
+    }
+}
+
+/// "mint_address" подходит под "mint_address", "mintAddress" и "mint"
+fn names_field(field: &str, name: &str) -> bool {
+    let short = field.strip_suffix("_address");
+    [Some(field), short]
+        .into_iter()
+        .flatten()
+        .any(|candidate| candidate == name || camel_case(candidate) == name)
+}
+
+/// Поля события в порядке сериализации, затем необязательные
+fn known_fields() -> Vec<String> {
+    let mut fields: Vec<String> = match serde_json::to_value(CreateTransaction::default()) {
+        Ok(Value::Object(object)) => object.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    fields.extend(OPTIONAL_FIELDS.iter().map(|field| field.to_string()));
+    fields
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+
+use crate::config::Commitment;
+use crate::{CreateTransaction, EventType};
+
+/// Фильтр событий одного SSE подключения, задается параметрами запроса.
+/// Пустой фильтр пропускает все события.
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    creators: HashSet<String>,
+    /// Только mint с таким окончанием, например "pump"
+    mint_suffix: Option<String>,
+    /// Подстрока имени токена без учета регистра (хранится в нижнем регистре)
+    name_contains: Option<String>,
+    /// Точный символ токена. Migrate без метаданных не проходит ни этот фильтр, ни name_contains
+    symbol: Option<String>,
+    /// Только события подписки с этим commitment (при нескольких --commitment)
+    commitment: Option<Commitment>,
+    /// Только события этой программы (source), задается путем /events/:program
+    source: Option<String>,
+}
+
+impl EventFilter {
+    /// Разбирает query string; `creator` можно повторять: `?creator=A&creator=B`
+    pub fn from_query(query: Option<&str>) -> Self {
+        let mut filter = Self::default();
+        let Some(query) = query else {
+            return filter;
+        };
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match key.as_ref() {
+                "creator" => {
+                    filter.creators.insert(value.to_string());
+                }
+                "mint_suffix" => filter.mint_suffix = Some(value.to_string()),
+                "name_contains" => filter.name_contains = Some(value.to_lowercase()),
+                "symbol" => filter.symbol = Some(value.to_string()),
+                "commitment" => filter.commitment = Commitment::from_str(value, true).ok(),
+                _ => {}
+            }
+        }
+        filter
+    }
+
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Служебные upstream_reconnect доходят до всех: фильтры касаются токенов
+    pub fn matches(&self, create_tx: &CreateTransaction) -> bool {
+        if create_tx.event_type == EventType::UpstreamReconnect {
+            return true;
+        }
+        (self.creators.is_empty() || self.creators.contains(&create_tx.creator_address))
+            && self
+                .mint_suffix
+                .as_ref()
+                .is_none_or(|suffix| create_tx.mint_address.ends_with(suffix.as_str()))
+            && self
+                .name_contains
+                .as_ref()
+                .is_none_or(|part| create_tx.name.to_lowercase().contains(part.as_str()))
+            && self.symbol.as_ref().is_none_or(|symbol| create_tx.symbol == *symbol)
+            && self
+                .commitment
+                .is_none_or(|commitment| create_tx.commitment == Some(commitment))
+            && self.source.as_ref().is_none_or(|source| create_tx.source == *source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(name: &str, symbol: &str) -> CreateTransaction {
+        CreateTransaction {
+            event_type: EventType::Create,
+            source: "pump_fun".to_string(),
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn name_contains_ignores_case() {
+        let filter = EventFilter::from_query(Some("name_contains=DoG"));
+        assert!(filter.matches(&token("Hot Dog Coin", "HDC")));
+        assert!(filter.matches(&token("DOGE", "D")));
+        assert!(!filter.matches(&token("Cat", "CAT")));
+    }
+
+    #[test]
+    fn symbol_is_exact() {
+        let filter = EventFilter::from_query(Some("symbol=DOG"));
+        assert!(filter.matches(&token("Dog", "DOG")));
+        assert!(!filter.matches(&token("Dog", "dog")));
+        assert!(!filter.matches(&token("Dog", "DOGE")));
+    }
+
+    #[test]
+    fn metadata_filters_skip_migrate_but_not_upstream_reconnect() {
+        let filter = EventFilter::from_query(Some("name_contains=dog&symbol=DOG"));
+        assert!(filter.matches(&token("Dog", "DOG")));
+        let migrate = CreateTransaction {
+            event_type: EventType::Migrate,
+            ..Default::default()
+        };
+        assert!(!filter.matches(&migrate));
+        let reconnect = CreateTransaction {
+            event_type: EventType::UpstreamReconnect,
+            ..Default::default()
+        };
+        assert!(filter.with_source("other").matches(&reconnect));
+    }
+
+    #[test]
+    fn blank_values_are_ignored_and_source_applies() {
+        let filter = EventFilter::from_query(Some("name_contains=&symbol=%20"));
+        assert!(filter.matches(&token("Cat", "CAT")));
+        let filter = filter.with_source("pump_fun");
+        assert!(filter.matches(&token("Cat", "CAT")));
+        assert!(!filter.matches(&CreateTransaction {
+            source: "other".to_string(),
+            ..token("Cat", "CAT")
+        }));
+    }
+}
@@ -0,0 +1,174 @@
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use log::{debug, error};
+#[cfg(unix)]
+use log::warn;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use crate::config::Config;
+
+/// Адрес клиента Unix сокета для обработчиков с ConnectInfo: все такие клиенты локальные,
+/// поэтому делят один лимит `max_connections_per_ip`
+#[cfg(unix)]
+const UNIX_PEER: SocketAddr = SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+
+/// Пауза после ошибки accept вроде EMFILE, чтобы не крутить цикл, пока нет дескрипторов
+pub const ACCEPT_ERROR_DELAY: Duration = Duration::from_secs(1);
+
+/// Где слушает HTTP сервер (--http-unix-socket или --http-listen)
+pub enum HttpListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl HttpListener {
+    /// Вне Unix --http-unix-socket отклоняет Config::validate, поэтому здесь только TCP
+    pub async fn bind(config: &Config) -> io::Result<Self> {
+        #[cfg(unix)]
+        if let Some(path) = &config.http_unix_socket {
+            return bind_unix(path).map(|listener| Self::Unix(listener, path.clone()));
+        }
+        TcpListener::bind(config.http_listen).await.map(Self::Tcp)
+    }
+}
+
+/// Занимает путь сокета. Файл, оставшийся после упавшего процесса (никто не принимает
+/// подключения), удаляется; сокет живого процесса не трогаем - bind вернет ошибку
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> io::Result<UnixListener> {
+    let is_socket = std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket());
+    if is_socket && std::os::unix::net::UnixStream::connect(path).is_err() {
+        warn!("🧹 Удаляем старый файл сокета {}", path.display());
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+/// Аналог axum::serve (в axum 0.7 нет настроек hyper и Unix сокетов) с HTTP/1 и HTTP/2
+/// по настройкам --http2-* и --http-tcp-keepalive-secs. Ошибки accept только логируются.
+/// После `shutdown` перестает принимать подключения (Unix сокет удаляется) и ждет открытые
+pub async fn serve(
+    listener: HttpListener,
+    app: Router,
+    config: &Config,
+    shutdown: CancellationToken,
+) {
+    let builder = connection_builder(config);
+    let graceful = GracefulShutdown::new();
+    let keepalive = (config.http_tcp_keepalive_secs > 0).then(|| {
+        let time = Duration::from_secs(config.http_tcp_keepalive_secs);
+        TcpKeepalive::new().with_time(time).with_interval(time)
+    });
+    loop {
+        let accepted = tokio::select! {
+            result = accept(&listener) => result,
+            _ = shutdown.cancelled() => break,
+        };
+        match accepted {
+            Ok(Accepted::Tcp(stream, peer)) => {
+                if let Some(keepalive) = &keepalive {
+                    if let Err(e) = SockRef::from(&stream).set_tcp_keepalive(keepalive) {
+                        debug!("Не удалось включить TCP keepalive для {}: {}", peer, e);
+                    }
+                }
+                serve_connection(&builder, &graceful, stream, app.clone(), peer);
+            }
+            #[cfg(unix)]
+            Ok(Accepted::Unix(stream)) => serve_connection(&builder, &graceful, stream, app.clone(), UNIX_PEER),
+            Err(e) if is_connection_error(&e) => debug!("HTTP подключение сброшено до accept: {}", e),
+            Err(e) => {
+                error!("HTTP ошибка accept: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+            }
+        }
+    }
+    #[cfg(unix)]
+    if let HttpListener::Unix(listener, path) = listener {
+        drop(listener);
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("⚠️ Не удалось удалить файл сокета {}: {}", path.display(), e);
+        }
+    }
+    graceful.shutdown().await;
+}
+
+fn connection_builder(config: &Config) -> auto::Builder<TokioExecutor> {
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    let keepalive_interval =
+        (config.http2_keepalive_interval_secs > 0).then(|| Duration::from_secs(config.http2_keepalive_interval_secs));
+    // Без таймера hyper паникует на первом HTTP/2 подключении с keep-alive
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .max_concurrent_streams(config.http2_max_concurrent_streams)
+        .keep_alive_interval(keepalive_interval)
+        .keep_alive_timeout(Duration::from_secs(config.http2_keepalive_timeout_secs));
+    builder
+}
+
+enum Accepted {
+    Tcp(tokio::net::TcpStream, SocketAddr),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+async fn accept(listener: &HttpListener) -> io::Result<Accepted> {
+    match listener {
+        HttpListener::Tcp(listener) => listener.accept().await.map(|(stream, peer)| Accepted::Tcp(stream, peer)),
+        #[cfg(unix)]
+        HttpListener::Unix(listener, _) => listener.accept().await.map(|(stream, _)| Accepted::Unix(stream)),
+    }
+}
+
+/// Адрес клиента кладется в ConnectInfo, как у into_make_service_with_connect_info
+/// (нужен для лимита подключений на IP). Upgrades нужны для /ws
+fn serve_connection<S>(
+    builder: &auto::Builder<TokioExecutor>,
+    graceful: &GracefulShutdown,
+    stream: S,
+    app: Router,
+    peer: SocketAddr,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = app.map_request(move |mut request: axum::http::Request<hyper::body::Incoming>| {
+        request.extensions_mut().insert(ConnectInfo(peer));
+        request
+    });
+    let connection = builder
+        .serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(service))
+        .into_owned();
+    let connection = graceful.watch(connection);
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("HTTP подключение {} закрыто с ошибкой: {}", peer, e);
+        }
+    });
+}
+
+/// Как у axum::serve: ошибки отдельного подключения не мешают принимать следующие
+pub fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+    )
+}
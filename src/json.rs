@@ -0,0 +1,57 @@
+//! JSON событий для клиентов и sinks: ключи в snake_case (как в структурах) или camelCase (--json-case)
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::JsonCase;
+
+pub fn to_value<T: Serialize>(value: &T, case: JsonCase) -> serde_json::Result<Value> {
+    let value = serde_json::to_value(value)?;
+    Ok(match case {
+        JsonCase::Snake => value,
+        JsonCase::Camel => camel_keys(value),
+    })
+}
+
+/// В snake_case сериализуем напрямую, без промежуточного Value
+pub fn to_string<T: Serialize>(value: &T, case: JsonCase) -> serde_json::Result<String> {
+    match case {
+        JsonCase::Snake => serde_json::to_string(value),
+        JsonCase::Camel => serde_json::to_string(&to_value(value, case)?),
+    }
+}
+
+pub fn to_vec<T: Serialize>(value: &T, case: JsonCase) -> serde_json::Result<Vec<u8>> {
+    to_string(value, case).map(String::into_bytes)
+}
+
+/// Переименовывает ключи объектов на всех уровнях; значения (например event_type "create") не трогает
+fn camel_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (camel_case(&key), camel_keys(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(camel_keys).collect()),
+        other => other,
+    }
+}
+
+/// "mint_address" -> "mintAddress"
+pub fn camel_case(key: &str) -> String {
+    let mut result = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
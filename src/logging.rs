@@ -0,0 +1,47 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use log::kv::{Key, Value, VisitSource};
+
+/// Формат логов: text - прежний человекочитаемый, json - по объекту на строку (для Loki и т.п.)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+pub fn init(format: LogFormat) {
+    // Устанавливаем уровень логирования по умолчанию, если не задан
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "info");
+    }
+    let mut builder = env_logger::Builder::from_default_env();
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut fields = serde_json::Map::new();
+            fields.insert("timestamp".into(), buf.timestamp_millis().to_string().into());
+            fields.insert("level".into(), record.level().as_str().into());
+            fields.insert("target".into(), record.target().into());
+            fields.insert("message".into(), record.args().to_string().into());
+            // Контекст (mint, signature, slot...) передается как key-values в log макросах
+            let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+            writeln!(buf, "{}", serde_json::Value::Object(fields))
+        });
+    }
+    builder.init();
+}
+
+struct JsonFields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        let value = match value.to_u64() {
+            Some(number) => number.into(),
+            None => value.to_string().into(),
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+/// Строки логов, выведенные самой программой (а не вложенными в нее вызовами).
+/// Логи идут по строке на запись: отслеживаем стек вызовов по "Program <id> invoke [n]"
+/// и "success"/"failed"; служебные строки invoke/success/failed в результат не входят.
+pub fn program_lines<'a>(log_messages: &'a [String], program_id: &str) -> Vec<&'a str> {
+    let mut stack: Vec<&str> = Vec::new();
+    let mut lines = Vec::new();
+    for line in log_messages {
+        if let Some((id, tail)) = line.strip_prefix("Program ").and_then(|rest| rest.split_once(' ')) {
+            if tail.starts_with("invoke [") {
+                stack.push(id);
+                continue;
+            }
+            if tail == "success" || tail.starts_with("failed") {
+                stack.pop();
+                continue;
+            }
+        }
+        if stack.last() == Some(&program_id) {
+            lines.push(line.as_str());
+        }
+    }
+    lines
+}
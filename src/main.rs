@@ -1,147 +1,735 @@
+mod auth;
+mod capture;
+mod compression;
+mod config;
+mod creators;
+mod dedup;
+mod fields;
+mod filter;
+mod http_server;
+mod json;
+mod logging;
+mod logs;
+mod metrics;
+mod pump;
+mod replay;
+mod sinks;
+mod state;
+mod telemetry;
+
 use anyhow::Result;
-use futures::{SinkExt, StreamExt};
-use log::{error, info, warn};
+use futures::SinkExt;
+use log::{debug, error, info, trace, warn};
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio;
 use tokio::sync::broadcast;
-use bs58;
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, RawQuery, State,
+    },
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
     response::{Response, IntoResponse},
-    routing::get,
-    Router,
+    routing::{get, post},
+    Json, Router,
 };
-use tokio_stream::{wrappers::BroadcastStream, StreamExt as TokioStreamExt};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, IntervalStream};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use serde::{Deserialize, Serialize};
-use yellowstone_grpc_client::{GeyserGrpcClient, ClientTlsConfig};
+use yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError};
 use yellowstone_grpc_proto::prelude::{
-    CommitmentLevel, SubscribeRequest, SubscribeRequestFilterTransactions,
-    SubscribeUpdate, subscribe_update::UpdateOneof,
+    CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterSlots,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateTransaction,
+    SubscribeUpdateTransactionInfo, TransactionStatusMeta,
+    subscribe_update::UpdateOneof,
 };
 
+use crate::config::{Commitment, Config, JsonCase, ProgramConfig};
+use crate::fields::FieldSet;
+use crate::http_server::HttpListener;
+use crate::filter::EventFilter;
+use crate::metrics::{Metrics, Missing};
+use crate::pump::TokenMetadata;
+use crate::state::{AppState, BroadcastEvent, ConnectionGuard, ConnectionLimit, SharedState};
+
+/// Тип события: создание токена, его миграция с bonding curve, обновление отслеживаемого аккаунта
+/// или служебное сообщение о разрыве GRPC подписки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    #[default]
+    Create,
+    Migrate,
+    Account,
+    UpstreamReconnect,
+}
+
+impl EventType {
+    /// Create и Migrate из транзакций; account и upstream_reconnect - служебные обновления,
+    /// они не попадают в /recent, повтор по Last-Event-ID и счетчик --probe
+    pub fn is_token_event(self) -> bool {
+        matches!(self, EventType::Create | EventType::Migrate)
+    }
+
+    /// Имя как в JSON поле event_type
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventType::Create => "create",
+            EventType::Migrate => "migrate",
+            EventType::Account => "account",
+            EventType::UpstreamReconnect => "upstream_reconnect",
+        }
+    }
+}
+
+/// Разрыв GRPC подписки: чем закончилась попытка и через сколько следующая
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamStatus {
+    endpoint: String,
+    reason: String,
+    backoff_ms: u64,
+}
+
+/// Состояние аккаунта из account подписки (--watch-account)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct CreateTransaction {
+pub struct AccountUpdate {
+    pubkey: String,
+    owner: String,
+    lamports: u64,
+    /// Данные аккаунта в base64 (только диапазоны --account-data-slice, если заданы)
+    data: String,
+    write_version: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateTransaction {
+    event_type: EventType,
+    /// Имя программы-источника (см. --program-name и --program)
+    source: String,
     signature: String,
     mint_address: String,
+    /// Mint - vanity адрес Pump.fun (см. `is_pump_vanity`). false означает, что такого mint среди
+    /// кандидатов не нашлось и выбран запасной (см. `created_mint`)
+    is_vanity: bool,
     creator_address: String,
+    /// creator_address и есть плательщик комиссии (см. `fee_payer`); false - создателя
+    /// указала программа (CreateEvent), а платил другой подписант, например бандлер или релейер
+    is_fee_payer_creator: bool,
     slot: u64,
+    /// Сколько слотов между вершиной цепи и slot события в момент обнаружения
+    slot_lag: u64,
+    /// Commitment подписки, с которой пришло событие; нет у событий из --replay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commitment: Option<Commitment>,
+    /// Метаданные из Create инструкции; пустые, если декодировать не удалось или это миграция
+    name: String,
+    symbol: String,
+    uri: String,
+    /// PDA bonding curve токена, пустая строка если вывести не удалось
+    bonding_curve: String,
+    /// Token account bonding curve с ее токенами (см. `pump::bonding_curve_vault`), пустая строка
+    /// если bonding curve неизвестна
+    bonding_curve_vault: String,
+    /// Metaplex metadata PDA mint (см. `pump::metadata_address`), пустая строка если mint не pubkey
+    metadata_address: String,
+    /// Инструкция по совпавшему маркеру в snake_case: "create", "create_v2", "migrate"
+    instruction_variant: String,
+    /// Сколько SOL создатель потратил на покупку в той же транзакции, 0 если покупки не было
+    dev_buy_lamports: u64,
+    /// Supply нового mint после Create в минимальных единицах (см. `mint_supply`), None если
+    /// в post_token_balances его нет или это не Create
+    initial_supply: Option<u64>,
+    /// decimals нового mint, None в тех же случаях
+    decimals: Option<u8>,
+    /// Комиссия транзакции, лампорты
+    fee: u64,
+    /// Потраченные compute units, если эндпоинт их передает
+    compute_units_consumed: Option<u64>,
+    /// Транзакция упала (meta.err); такие события бывают только с --include-failed
+    failed: bool,
+    /// Локальное время обнаружения, unix millis
+    received_at: u64,
+    /// Все ключи транзакции (статичные и из lookup tables), только с --verbose-events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    account_keys: Vec<String>,
+    /// Только у событий account: новое состояние аккаунта
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account: Option<AccountUpdate>,
+    /// Только у событий upstream_reconnect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    upstream: Option<UpstreamStatus>,
+}
+
+/// Окончание vanity адресов, которые Pump.fun выдает своим mint
+const VANITY_SUFFIX: &str = "pump";
+
+/// Vanity mint Pump.fun: корректный pubkey (base58 ровно 32 байта) длиной 43-44 символа,
+/// оканчивающийся на "pump" с учетом регистра. Случайный 32-байтный ключ почти всегда кодируется
+/// в 43-44 символа, короче бывают только ключи с нулевыми старшими байтами, которые при подборе
+/// vanity суффикса не получаются. Поэтому строки "...pump" другой длины, не base58 или с
+/// "PUMP"/"Pump" не считаются vanity и не вытесняют настоящий mint при выборе кандидата
+fn is_pump_vanity(mint: &str) -> bool {
+    (43..=44).contains(&mint.len())
+        && mint.ends_with(VANITY_SUFFIX)
+        && bs58::decode(mint).into_vec().is_ok_and(|bytes| bytes.len() == 32)
 }
 
-type AppState = Arc<broadcast::Sender<CreateTransaction>>;
+/// Больше событий в одном batch кадре не собираем, даже если окно еще не закончилось
+const SSE_BATCH_MAX: usize = 100;
+
+/// Retry-After для отказа по --max-subscribers, секунды
+const SUBSCRIBERS_FULL_RETRY_AFTER_SECS: u64 = 5;
+
+/// Сколько ждем закрытия SSE соединений и GRPC задачи при остановке
+const SHUTDOWN_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Устанавливаем уровень логирования по умолчанию, если не задан
-    if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", "info");
+    let config = Arc::new(Config::load()?);
+
+    logging::init(config.log_format);
+
+    // Офлайн режим: без сети и HTTP сервера
+    if let Some(path) = &config.replay {
+        telemetry::discard_spans();
+        return replay::run(config.clone(), path);
     }
-    env_logger::init();
     
     // Устанавливаем CryptoProvider для rustls
     rustls::crypto::aws_lc_rs::default_provider()
         .install_default()
         .expect("Failed to install crypto provider");
+    let telemetry = telemetry::Telemetry::init(&config)?;
 
     // Создаем broadcast channel для отправки Create транзакций
-    let (tx, _) = broadcast::channel::<CreateTransaction>(1000);
-    let state = Arc::new(tx);
+    let (tx, _) = broadcast::channel::<BroadcastEvent>(config.channel_capacity as usize);
+    let state = Arc::new(SharedState::new(config.clone(), tx));
+    apply_live_settings(&state, &config)?;
+    spawn_sighup_handler(state.clone())?;
 
-    // Запускаем HTTP сервер для SSE
+    // Запускаем HTTP сервер для SSE: на Unix сокете, если задан, иначе на TCP адресе
+    let listener = match HttpListener::bind(&config).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            match &config.http_unix_socket {
+                Some(path) => error!("❌ Не удалось занять сокет {}: {}", path.display(), e),
+                None => error!("❌ Не удалось занять адрес {}: {}", config.http_listen, e),
+            }
+            std::process::exit(1);
+        }
+    };
     let state_clone = state.clone();
-    tokio::spawn(async move {
-        let app = Router::new()
+    let http_config = config.clone();
+    let http_shutdown = state.shutdown.clone();
+    let cors = cors_layer(&config)?;
+    let http_task = tokio::spawn(async move {
+        // Маршруты с событиями закрыты ключом (если задан), пробы и метрики остаются открытыми
+        let protected = Router::new()
             .route("/events", get(sse_handler))
+            .route("/events/:program", get(program_sse_handler))
+            .route("/ws", get(ws_handler))
+            .route("/recent", get(recent_handler))
+            .route("/leaderboard", get(leaderboard_handler))
+            .route("/debug/process", post(debug_process_handler))
+            .route("/config", get(config_handler))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state_clone.clone(),
+                auth::require_api_key,
+            ));
+        let app = Router::new()
             .route("/health", get(health_handler))
+            .route("/ready", get(ready_handler))
+            .route("/metrics", get(metrics_handler))
+            .route("/stats", get(stats_handler))
+            .merge(protected)
+            // JSON ответы сжимаются по Accept-Encoding; SSE этот слой пропускает, см. compression.rs
+            .layer(CompressionLayer::new())
+            .layer(cors)
             .with_state(state_clone);
 
-        let listener = tokio::net::TcpListener::bind("0.0.0.0:8724").await.unwrap();
-        info!("🌐 HTTP сервер запущен на http://0.0.0.0:8724/events");
-        axum::serve(listener, app).await.unwrap();
+        match &listener {
+            HttpListener::Tcp(_) => info!("🌐 HTTP сервер запущен на http://{}/events", http_config.http_listen),
+            #[cfg(unix)]
+            HttpListener::Unix(_, path) => {
+                info!("🌐 HTTP сервер запущен на Unix сокете {} (/events)", path.display())
+            }
+        }
+        // Ошибки accept сервер переживает сам, поэтому возвращается только после остановки
+        http_server::serve(listener, app, &http_config, http_shutdown).await;
     });
 
+    // Запускаем внешние sinks, каждый со своей подпиской на канал
+    sinks::webhook::spawn_all(&state)?;
+    sinks::sqlite::spawn(&state)?;
+    sinks::file::spawn(&state).await?;
+    sinks::redis::spawn(&state)?;
+    sinks::nats::spawn(&state)?;
+    #[cfg(feature = "kafka")]
+    sinks::kafka::spawn(&state)?;
+    sinks::tcp::spawn(&state).await?;
+    sinks::statsd::spawn(&state).await?;
+
     // Запускаем GRPC подписку
     let grpc_state = state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = run_grpc_subscription(grpc_state).await {
-            error!("GRPC ошибка: {}", e);
+    let grpc_config = config.clone();
+    let (capture, capture_task) = capture::spawn(&config).await?.unzip();
+    let grpc_task = tokio::spawn(async move {
+        // По подписке на каждый commitment, все в один канал; ошибка одной останавливает сервер
+        let subscriptions = grpc_config.commitments.iter().enumerate().map(|(i, commitment)| {
+            let capture = capture.as_ref().filter(|_| i == 0);
+            run_grpc_subscription(grpc_config.clone(), grpc_state.clone(), *commitment, capture)
+        });
+        let result = futures::future::try_join_all(subscriptions).await;
+        if let Err(e) = &result {
+            error!("GRPC ошибка: {:#}", e);
+            grpc_state.shutdown.cancel();
         }
+        result.is_ok()
     });
 
-    // Ждем бесконечно
-    tokio::signal::ctrl_c().await?;
+    // Ждем сигнала остановки (или завершения --probe), затем даем SSE клиентам получить прощальное событие
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = state.shutdown.cancelled() => {}
+    }
     info!("Остановка сервера...");
+    state.shutdown.cancel();
+    let drain = async {
+        let tasks = tokio::join!(http_task, grpc_task);
+        // GRPC задача завершилась и отпустила Capture: ждем, пока запишется хвост очереди
+        if let Some(capture_task) = capture_task {
+            let _ = capture_task.await;
+        }
+        tasks
+    };
+    let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, drain).await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    match drained {
+        Ok((Ok(()), Ok(true))) => {}
+        Ok(_) => std::process::exit(1),
+        Err(_) => warn!("Не дождались завершения задач за {:?}, выходим", SHUTDOWN_TIMEOUT),
+    }
+    Ok(())
+}
+
+/// Применяет LIVE_SETTINGS из `config` (списки создателей читаются заново).
+/// При ошибке остаются прежние значения
+fn apply_live_settings(state: &SharedState, config: &Config) -> Result<()> {
+    let creator_list = creators::CreatorList::load(config)?;
+    if let Some(list) = &creator_list {
+        info!("📋 {} создателей: {} адресов из {}", list.kind(), list.len(), list.path().display());
+    }
+    *state.live.write().unwrap() = state::LiveSettings {
+        min_dev_buy_lamports: config.min_dev_buy_lamports,
+        creator_list,
+    };
     Ok(())
 }
 
-async fn run_grpc_subscription(state: AppState) -> Result<()> {
-    let endpoint = "http://fr.grpc.gadflynode.com:25565";
-    let mut backoff = tokio::time::Duration::from_secs(1);
+/// Перечитывает конфигурацию (аргументы, --config, окружение): фильтры применяются сразу,
+/// про остальные изменения предупреждаем, что нужен перезапуск
+#[cfg(unix)]
+fn reload_config(state: &SharedState) -> Result<()> {
+    let config = Config::load()?;
+    // Значения берем из redacted, чтобы секреты не попали в лог
+    let (old, new) = (state.config.redacted(), config.redacted());
+    for setting in state.config.restart_required(&config) {
+        warn!(
+            "⚠️ {} изменен ({} -> {}), но на ходу не применяется: нужен перезапуск",
+            setting, old[&setting], new[&setting]
+        );
+    }
+    apply_live_settings(state, &config)
+}
+
+/// SIGHUP перечитывает конфигурацию без перезапуска, соединения GRPC и SSE не рвутся
+#[cfg(unix)]
+fn spawn_sighup_handler(state: AppState) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                received = hangup.recv() => {
+                    if received.is_none() {
+                        return;
+                    }
+                }
+                _ = state.shutdown.cancelled() => return,
+            }
+            info!("🔄 SIGHUP: перечитываем конфигурацию");
+            if let Err(e) = reload_config(&state) {
+                error!("❌ Не удалось перечитать конфигурацию, оставляем прежнюю: {:#}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// SIGHUP есть только в Unix: на Windows LIVE_SETTINGS меняются лишь перезапуском
+#[cfg(not(unix))]
+fn spawn_sighup_handler(_state: AppState) -> Result<()> {
+    info!("config reload via SIGHUP unsupported on this platform");
+    Ok(())
+}
+
+/// CORS для браузерных дашбордов с другого origin
+fn cors_layer(config: &Config) -> Result<CorsLayer> {
+    let allow_origin = if config.cors_origins.iter().any(|origin| origin.trim() == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .cors_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin.trim())
+                    .map_err(|e| anyhow::anyhow!("Invalid CORS origin {:?}: {}", origin, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers(Any))
+}
+
+async fn run_grpc_subscription(
+    config: Arc<Config>,
+    state: AppState,
+    commitment: Commitment,
+    capture: Option<&capture::Capture>,
+) -> Result<()> {
+    let base = tokio::time::Duration::from_millis(config.reconnect_base_ms);
+    let max = tokio::time::Duration::from_millis(config.reconnect_max_ms);
+    let initial_max = tokio::time::Duration::from_millis(config.initial_reconnect_max_ms).min(max);
+    let mut backoff = base.min(initial_max);
+    // До первого подключения ошибки - это недоступность эндпоинта при старте, а не обрыв
+    let mut ever_connected = false;
+    let endpoints: Vec<&str> = config.endpoints.iter().map(|e| e.trim()).collect();
+    let mut current = 0;
+    let mut failures = 0;
+    // Повтор истории нужен только при старте; сбрасывается после первого обновления
+    let mut from_slot = config.from_slot;
 
     loop {
-        match subscribe_once(endpoint, state.clone()).await {
+        let endpoint = endpoints[current];
+        let mut connected = false;
+        let result = tokio::select! {
+            result = subscribe_once(
+                &config,
+                endpoint,
+                state.clone(),
+                commitment,
+                capture,
+                &mut from_slot,
+                &mut connected,
+            ) => result,
+            _ = state.shutdown.cancelled() => {
+                info!("GRPC подписка {} остановлена", commitment.as_str());
+                return Ok(());
+            }
+        };
+        // --probe и --signature сами останавливают сервер после результата: это не обрыв
+        if state.shutdown.is_cancelled() && result.is_ok() {
+            return Ok(());
+        }
+        if connected {
+            // Эндпоинт работал: начинаем отсчет неудач и backoff заново
+            ever_connected = true;
+            failures = 0;
+            backoff = base;
+        } else {
+            failures += 1;
+        }
+        let delay = jittered(backoff);
+        let reason = match result {
             Ok(_) => {
-                backoff = tokio::time::Duration::from_secs(1);
-                warn!("GRPC соединение закрыто, переподключение через {:?}...", backoff);
+                warn!(
+                    "GRPC соединение {} ({}) закрыто сервером, переподключение через {:?}...",
+                    endpoint,
+                    commitment.as_str(),
+                    delay
+                );
+                "stream closed by server".to_string()
+            }
+            // В --probe ошибка подключения - это результат проверки, а не повод ждать
+            Err(e) if config.probe.is_some() => {
+                return Err(e.context(format!("probe of {} failed", endpoint)));
+            }
+            Err(e) if connected => {
+                error!(
+                    "GRPC соединение {} ({}) оборвалось: {} (переподключение через {:?})",
+                    endpoint,
+                    commitment.as_str(),
+                    e,
+                    delay
+                );
+                format!("{:#}", e)
             }
             Err(e) => {
-                error!("GRPC ошибка: {} (переподключение через {:?})", e, backoff);
+                let phase = if ever_connected { "" } else { " при старте" };
+                error!(
+                    "GRPC не удалось подключиться{} к {} ({}): {} (повтор через {:?})",
+                    phase,
+                    endpoint,
+                    commitment.as_str(),
+                    e,
+                    delay
+                );
+                format!("connect failed: {:#}", e)
             }
+        };
+        // Потребители отмечают по этому событию пропуск в данных вместе с причиной
+        let mut event = upstream_reconnect_event(endpoint, reason, delay);
+        event.commitment = Some(commitment);
+        state.publish(event);
+        Metrics::inc(&state.metrics.grpc_reconnects);
+        if endpoints.len() > 1 && failures >= config.endpoint_failover_after {
+            current = (current + 1) % endpoints.len();
+            failures = 0;
+            warn!(
+                "🔀 {} не отвечает {} раз подряд, переключаемся на {}",
+                endpoint, config.endpoint_failover_after, endpoints[current]
+            );
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = state.shutdown.cancelled() => return Ok(()),
         }
-        tokio::time::sleep(backoff).await;
-        backoff = std::cmp::min(backoff * 2, tokio::time::Duration::from_secs(30));
+        backoff = std::cmp::min(backoff * 2, if ever_connected { max } else { initial_max });
     }
 }
 
-async fn subscribe_once(endpoint: &str, state: AppState) -> Result<()> {
-    let mut client = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
-        .tls_config(ClientTlsConfig::new().with_native_roots())?
-        .connect()
-        .await?;
+fn upstream_reconnect_event(endpoint: &str, reason: String, delay: tokio::time::Duration) -> CreateTransaction {
+    CreateTransaction {
+        event_type: EventType::UpstreamReconnect,
+        source: "upstream".to_string(),
+        received_at: unix_millis(),
+        upstream: Some(UpstreamStatus {
+            endpoint: endpoint.to_string(),
+            reason,
+            backoff_ms: delay.as_millis() as u64,
+        }),
+        ..Default::default()
+    }
+}
 
-    info!("✅ GRPC подключен: {}", endpoint);
+/// Full jitter: случайная задержка от 0 до `backoff`, чтобы экземпляры
+/// не переподключались к перезапущенной ноде одновременно
+fn jittered(backoff: tokio::time::Duration) -> tokio::time::Duration {
+    let millis = backoff.as_millis() as u64;
+    tokio::time::Duration::from_millis(rand::Rng::random_range(&mut rand::rng(), 0..=millis))
+}
 
-    // Фильтр для Pump.fun Create транзакций
-    let pump_fun_program_id = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
-    
-    let mut transactions_filters: HashMap<String, SubscribeRequestFilterTransactions> = HashMap::new();
-    transactions_filters.insert(
-        "pump_fun".to_string(),
-        SubscribeRequestFilterTransactions {
-            vote: Some(false),
-            failed: Some(false),
-            signature: None,
-            account_include: vec![pump_fun_program_id.to_string()],
-            account_exclude: vec![],
-            account_required: vec![],
+/// `connected` выставляется после успешного подключения, даже если поток потом оборвался.
+/// `from_slot` сбрасывается, как только сервер начал слать обновления или отказал в повторе
+async fn subscribe_once(
+    config: &Config,
+    endpoint: &str,
+    state: AppState,
+    commitment: Commitment,
+    capture: Option<&capture::Capture>,
+    from_slot: &mut Option<u64>,
+    connected: &mut bool,
+) -> Result<()> {
+    let mut builder = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
+        .x_token(config.x_token.clone())?
+        .max_decoding_message_size(config.grpc_max_message_bytes);
+    // http:// - локальная нода без TLS (например Geyser плагин на localhost)
+    let plaintext = endpoint.starts_with("http://");
+    if !plaintext {
+        builder = builder.tls_config(config.tls_config()?)?;
+    }
+    if let Some(encoding) = config.grpc_compression.encoding() {
+        builder = builder.accept_compressed(encoding);
+    }
+    let mut client = builder.connect().await?;
+
+    info!(
+        "✅ GRPC подключен: {} ({}, {}, сжатие ответов: {})",
+        endpoint,
+        commitment.as_str(),
+        if plaintext { "без TLS" } else { "TLS" },
+        config.grpc_compression.as_str()
+    );
+    *connected = true;
+    let _connection = state.metrics.grpc_connection();
+
+    // По фильтру на каждую отслеживаемую программу или именованные из --tx-filter
+    let programs = config.programs()?;
+
+    let transactions_filters = if let Some(signature) = &config.signature {
+        // Одна транзакция для диагностики: без фильтра по программе и вместе с упавшими,
+        // чтобы объяснить, почему детектор ее отверг
+        info!("🔎 Ждем транзакцию {} (сервер присылает только новые транзакции)", signature);
+        HashMap::from([(
+            "signature".to_string(),
+            SubscribeRequestFilterTransactions {
+                signature: Some(signature.clone()),
+                ..Default::default()
+            },
+        )])
+    } else {
+        config.transactions_filters(&programs)?
+    };
+    let mut filter_names: Vec<_> = transactions_filters.keys().cloned().collect();
+    filter_names.sort();
+
+    let (mut subscribe_tx, mut updates_stream) = client.subscribe().await.map_err(|e| match e {
+        GeyserGrpcClientError::TonicStatus(status) => status_error(status),
+        other => other.into(),
+    })?;
+
+    // Слоты нужны, чтобы знать вершину цепи независимо от потока транзакций
+    let mut slots_filters: HashMap<String, SubscribeRequestFilterSlots> = HashMap::new();
+    slots_filters.insert(
+        "chain_tip".to_string(),
+        SubscribeRequestFilterSlots {
+            filter_by_commitment: Some(true),
+            interslot_updates: Some(false),
         },
     );
 
-    let (mut subscribe_tx, mut updates_stream) = client.subscribe().await?;
+    // Отдельные аккаунты (например bonding curve) отслеживаем по их обновлениям, а не транзакциям
+    let mut accounts_filters: HashMap<String, SubscribeRequestFilterAccounts> = HashMap::new();
+    if !config.watch_accounts.is_empty() {
+        accounts_filters.insert(
+            "watched".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: config.watch_accounts.clone(),
+                ..Default::default()
+            },
+        );
+    }
 
     let request = SubscribeRequest {
         transactions: transactions_filters,
-        commitment: Some(CommitmentLevel::Processed as i32),
+        slots: slots_filters,
+        accounts: accounts_filters,
+        commitment: Some(CommitmentLevel::from(commitment) as i32),
+        accounts_data_slice: config.data_slices()?,
+        from_slot: *from_slot,
         ..Default::default()
     };
 
     subscribe_tx.send(request).await?;
-    info!("✅ Подписка на Pump.fun Create отправлена");
+    if let Some(slot) = from_slot {
+        info!("⏪ Запрошен повтор со слота {}", slot);
+    }
+    for program in &programs {
+        info!("✅ Подписка на Create транзакции {} ({}) отправлена", program.source, program.program_id);
+    }
+    if !config.tx_filters.is_empty() {
+        info!("🧩 Фильтры транзакций: {}", filter_names.join(", "));
+    }
+
+    // Некоторые эндпоинты закрывают подписку, если клиент долго ничего не отправляет
+    let ping_period = tokio::time::Duration::from_secs(config.grpc_ping_secs);
+    let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + ping_period, ping_period);
+    let mut ping_id = 0;
 
-    while let Some(message) = futures::StreamExt::next(&mut updates_stream).await {
+    // Эндпоинт может перестать слать обновления, не закрывая поток: тогда next() ждет вечно.
+    // Пинги выше гарантируют хотя бы pong, так что тишина дольше таймаута означает зависание
+    let idle_timeout = tokio::time::Duration::from_secs(config.grpc_idle_timeout_secs);
+    let idle = tokio::time::sleep(idle_timeout);
+    tokio::pin!(idle);
+
+    loop {
+        let message = tokio::select! {
+            message = futures::StreamExt::next(&mut updates_stream) => message,
+            _ = &mut idle, if !idle_timeout.is_zero() => {
+                warn!("⏱️ Нет обновлений {:?}, переподключение по idle timeout", idle_timeout);
+                return Err(anyhow::anyhow!("no updates for {:?} (idle timeout)", idle_timeout));
+            }
+            _ = ping.tick() => {
+                ping_id += 1;
+                subscribe_tx.send(ping_request(ping_id)).await?;
+                debug!("🏓 GRPC ping id={}", ping_id);
+                continue;
+            }
+        };
+        let Some(message) = message else {
+            break;
+        };
+        idle.as_mut().reset(tokio::time::Instant::now() + idle_timeout);
         match message {
             Ok(update) => {
-                if let Some(create_tx) = process_update(update) {
+                Metrics::inc(&state.metrics.updates_received);
+                // Сервер принял from_slot: после переподключения повтор уже не нужен, идем живыми данными
+                from_slot.take();
+                if let Some(capture) = capture {
+                    capture.record(&update);
+                }
+                if config.signature.is_some() {
+                    if let Some(UpdateOneof::Transaction(tx_info)) = &update.update_oneof {
+                        report_signature(tx_info, &programs, &state.metrics);
+                        state.shutdown.cancel();
+                        return Ok(());
+                    }
+                }
+                // Ping от сервера требует ответа, иначе часть балансировщиков рвет поток
+                if matches!(update.update_oneof, Some(UpdateOneof::Ping(_))) {
+                    subscribe_tx.send(ping_request(1)).await?;
+                }
+                let created_at_ms = update.created_at.as_ref().map(|ts| {
+                    (ts.seconds.max(0) as u64) * 1000 + (ts.nanos.max(0) as u64) / 1_000_000
+                });
+                if let Some(create_tx) = process_update(update, &programs, &state, Some(commitment)) {
+                    if is_stale(&state, &create_tx, created_at_ms) {
+                        continue;
+                    }
                     // Отправляем Create транзакцию через broadcast
-                    if state.send(create_tx.clone()).is_ok() {
-                        info!("📤 Отправлено Create: mint={} creator={}", create_tx.mint_address, create_tx.creator_address);
+                    let receivers = state.publish(create_tx.clone());
+                    if receivers > 0 {
+                        info!(
+                            mint = create_tx.mint_address.as_str(),
+                            creator = create_tx.creator_address.as_str(),
+                            signature = create_tx.signature.as_str(),
+                            slot = create_tx.slot,
+                            receivers = receivers;
+                            "📤 Отправлено Create: mint={} creator={} получателей={}",
+                            create_tx.mint_address, create_tx.creator_address, receivers
+                        );
+                    } else {
+                        // broadcast отказывает только при отсутствии получателей: это не ошибка,
+                        // событие осталось в буфере /recent и дойдет до клиента с Last-Event-ID
+                        debug!(
+                            "Нет подписчиков, событие mint={} сохранено только в /recent",
+                            create_tx.mint_address
+                        );
+                        Metrics::inc(&state.metrics.broadcast_no_subscribers);
+                    }
+                    if let Some(probe) = config.probe {
+                        let published = state.published_count();
+                        info!("🔎 Probe: {}/{}", published, probe);
+                        if published >= probe {
+                            info!("✅ Probe завершен: получено {} событий", published);
+                            state.shutdown.cancel();
+                            return Ok(());
+                        }
                     }
                 }
             }
+            Err(e) if from_slot.is_some() && replay_unsupported(&e) => {
+                warn!(
+                    "⚠️ {} не поддерживает повтор с from_slot={} ({}), продолжаем только с живыми данными",
+                    endpoint,
+                    from_slot.take().unwrap_or_default(),
+                    e.message()
+                );
+                return Err(status_error(e));
+            }
             Err(e) => {
                 error!("Ошибка стрима: {:?}", e);
-                return Err(anyhow::anyhow!("GRPC stream error: {}", e));
+                return Err(status_error(e));
             }
         }
     }
@@ -149,140 +737,1544 @@ async fn subscribe_once(endpoint: &str, state: AppState) -> Result<()> {
     Ok(())
 }
 
-async fn sse_handler(State(tx): State<AppState>) -> impl IntoResponse {
+/// --signature: печатает событие, которое получилось бы из транзакции, или причину отказа
+fn report_signature(tx_info: &SubscribeUpdateTransaction, programs: &[ProgramConfig], metrics: &Metrics) {
+    match process_transaction(tx_info, programs, metrics, &[]) {
+        Some(create_tx) => match serde_json::to_string_pretty(&create_tx) {
+            Ok(json) => println!("{}", json),
+            Err(e) => println!("Event serialization failed: {}", e),
+        },
+        None => println!("Rejected: {}", rejection_reason(tx_info, programs)),
+    }
+}
+
+/// Повторяет проверки `process_transaction` по шагам, чтобы назвать первую несработавшую
+fn rejection_reason(tx_info: &SubscribeUpdateTransaction, programs: &[ProgramConfig]) -> String {
+    let Some(meta) = tx_info.transaction.as_ref().and_then(|tx| tx.meta.as_ref()) else {
+        return "transaction has no meta".to_string();
+    };
+    if let Some(err) = meta.err.as_ref().filter(|_| programs.iter().all(|program| !program.include_failed)) {
+        return format!("transaction failed: {:?}", err);
+    }
+    if meta.log_messages.is_empty() {
+        return "transaction has no log messages".to_string();
+    }
+    let mut reasons = Vec::new();
+    for program in programs {
+        let invoked = meta
+            .log_messages
+            .iter()
+            .any(|line| line.starts_with(&format!("Program {} invoke", program.program_id)));
+        if !invoked {
+            reasons.push(format!("{}: program {} not invoked", program.source, program.program_id));
+        } else if detect_event(tx_info, program).is_none() {
+            reasons.push(format!(
+                "{}: program invoked but none of the markers {:?} / {:?} logged by it",
+                program.source, program.create_markers, program.migrate_markers
+            ));
+        } else {
+            reasons.push(format!(
+                "{}: event matched but no signature, creator or mint could be extracted",
+                program.source
+            ));
+        }
+    }
+    reasons.join("; ")
+}
+
+fn ping_request(id: i32) -> SubscribeRequest {
+    SubscribeRequest {
+        ping: Some(SubscribeRequestPing { id }),
+        ..Default::default()
+    }
+}
+
+/// Так отвечают эндпоинты без повтора или без нужного слота в истории
+fn replay_unsupported(status: &tonic::Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::InvalidArgument | tonic::Code::Unimplemented | tonic::Code::OutOfRange
+    )
+}
+
+/// Отказ сервера в авторизации выделяем отдельно от прочих ошибок транспорта
+fn status_error(status: tonic::Status) -> anyhow::Error {
+    match status.code() {
+        tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => anyhow::anyhow!(
+            "GRPC authentication failed (check x-token): {}",
+            status.message()
+        ),
+        _ => anyhow::anyhow!("GRPC stream error: {}", status),
+    }
+}
+
+async fn sse_handler(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    sse_response(state, peer, query, headers, None)
+}
+
+/// /events только с событиями одной программы (source из --program-name / --program)
+async fn program_sse_handler(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(program): Path<String>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    let programs = state.config.programs().unwrap_or_default();
+    if !programs.iter().any(|known| known.source == program) {
+        let known: Vec<_> = programs.iter().map(|known| known.source.as_str()).collect();
+        let message = format!("Unknown program {:?}, expected one of: {}", program, known.join(", "));
+        return (StatusCode::NOT_FOUND, message).into_response();
+    }
+    sse_response(state, peer, query, headers, Some(program))
+}
+
+fn sse_response(
+    state: AppState,
+    peer: SocketAddr,
+    query: Option<String>,
+    headers: HeaderMap,
+    program: Option<String>,
+) -> Response {
     use axum::body::Body;
+
+    let fields = match FieldSet::from_query(query.as_deref()) {
+        Ok(fields) => Arc::new(fields),
+        Err(message) => return (StatusCode::BAD_REQUEST, message).into_response(),
+    };
+    let ip = client_ip(&state, &headers, peer);
+    let guard = match ConnectionGuard::acquire(&state, ip) {
+        Ok(guard) => guard,
+        Err(limit) => return connection_refused(ip, limit),
+    };
+
+    // Фильтр свой у каждого подключения, канал общий (или программы с --partition-by-program)
+    let mut filter = EventFilter::from_query(query.as_deref());
+    if let Some(program) = &program {
+        filter = filter.with_source(program);
+    }
+    let case = state.config.json_case;
+
+    // При переподключении EventSource присылает id последнего полученного события
+    let last_event_id = headers
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    let (replay, rx) = state.subscribe_from(last_event_id, program.as_deref());
+    let replay = futures::stream::iter(replay.into_iter().map(Ok));
+    let stream = futures::StreamExt::chain(replay, BroadcastStream::new(rx));
     
-    let rx = tx.subscribe();
-    let stream = BroadcastStream::new(rx);
-    
-    let stream = futures::StreamExt::filter_map(stream, |result| {
+    let stream = futures::StreamExt::filter(stream, move |result| {
         futures::future::ready(match result {
-            Ok(create_tx) => {
-                if let Ok(json) = serde_json::to_string(&create_tx) {
-                    Some(Ok::<_, std::io::Error>(format!("data: {}\n\n", json)))
-                } else {
-                    None
-                }
-            }
-            Err(_) => None,
+            Ok(event) => filter.matches(&event.create_tx),
+            Err(_) => true,
         })
     });
+    let stream = if query_flag(query.as_deref(), "batch") {
+        // ?batch=1: события за окно --sse-batch-ms уходят одним кадром с JSON массивом
+        let window = tokio::time::Duration::from_millis(state.config.sse_batch_ms);
+        let batches = tokio_stream::StreamExt::chunks_timeout(stream, SSE_BATCH_MAX, window);
+        futures::StreamExt::boxed(futures::StreamExt::map(batches, move |batch| {
+            Ok::<_, std::io::Error>(sse_batch_frame(&batch, case, &fields))
+        }))
+    } else {
+        futures::StreamExt::boxed(futures::StreamExt::filter_map(stream, move |result| {
+            futures::future::ready(match result {
+                Ok(event) => sse_frame(&event, case, &fields).map(Ok::<_, std::io::Error>),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(lagged_frame(missed))),
+            })
+        }))
+    };
 
-    let body = Body::from_stream(stream);
-    
-    Response::builder()
+    // Комментарии игнорируются EventSource клиентами, но не дают прокси закрыть простаивающее соединение
+    let period = tokio::time::Duration::from_secs(state.config.sse_heartbeat_secs);
+    let heartbeats = IntervalStream::new(tokio::time::interval_at(
+        tokio::time::Instant::now() + period,
+        period,
+    ));
+    let heartbeats = futures::StreamExt::map(heartbeats, |_| {
+        Ok::<_, std::io::Error>(": keepalive\n\n".to_string())
+    });
+    let stream = futures::stream::select(stream, heartbeats);
+
+    // Первым кадром сообщаем конфигурацию сервера, чтобы клиент мог ее сверить
+    let hello = futures::stream::once(futures::future::ready(Ok::<_, std::io::Error>(hello_frame(&state))));
+    let stream = futures::StreamExt::chain(hello, stream);
+
+    // По истечении --sse-max-duration-secs закрываем поток, чтобы балансировщик перераспределил
+    // соединения; событие reconnect просит клиента переподключиться сразу (с Last-Event-ID)
+    let max_duration = tokio::time::Duration::from_secs(state.config.sse_max_duration_secs);
+    let expired = async move {
+        if max_duration.is_zero() {
+            futures::future::pending::<()>().await
+        } else {
+            tokio::time::sleep(max_duration).await
+        }
+    };
+    let stream = futures::StreamExt::take_until(stream, expired);
+    let reconnect = futures::stream::once(futures::future::ready(Ok::<_, std::io::Error>(
+        "event: reconnect\ndata: {}\n\n".to_string(),
+    )));
+    let stream = futures::StreamExt::chain(stream, reconnect);
+
+    // При остановке сервера завершаем поток, предупредив клиента отдельным событием
+    let stream = futures::StreamExt::take_until(stream, state.shutdown.clone().cancelled_owned());
+    // Поток мог закончиться и по --sse-max-duration-secs: тогда shutdown не шлем
+    let shutdown = state.shutdown.clone();
+    let goodbye = futures::StreamExt::filter_map(
+        futures::stream::once(async move { shutdown.is_cancelled() }),
+        |cancelled| {
+            futures::future::ready(
+                cancelled.then(|| Ok::<_, std::io::Error>("event: shutdown\ndata: {}\n\n".to_string())),
+            )
+        },
+    );
+    let stream = futures::StreamExt::chain(stream, goodbye);
+    // Место в лимите IP держит сам поток: освобождается, когда axum его дропнет
+    let stream = futures::StreamExt::map(stream, move |frame| {
+        let _ = &guard;
+        frame
+    });
+
+    let gzip = compression::accepts_gzip(&headers);
+    let body = if gzip {
+        Body::from_stream(compression::gzip_frames(stream))
+    } else {
+        Body::from_stream(stream)
+    };
+
+    let mut response = Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "text/event-stream")
         .header("Cache-Control", "no-cache")
         .header("Connection", "keep-alive")
-        .body(body)
-        .unwrap()
+        .header("Vary", "Accept-Encoding");
+    if gzip {
+        response = response.header("Content-Encoding", "gzip");
+    }
+    // Заголовки статические, так что ошибки не ожидается, но паника здесь уронила бы задачу сервера
+    match response.body(body) {
+        Ok(response) => response,
+        Err(e) => {
+            error!("❌ Не удалось собрать SSE ответ: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build event stream response\n").into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct HelloEvent<'a> {
+    version: &'static str,
+    programs: Vec<HelloProgram<'a>>,
+    /// Первый из --commitment, для клиентов, которые знают только одну подписку
+    commitment: Commitment,
+    commitments: &'a [Commitment],
+    latest_slot: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct HelloProgram<'a> {
+    source: &'a str,
+    program_id: &'a str,
+}
+
+fn hello_frame(state: &SharedState) -> String {
+    // Программы уже проверены при старте в Config::validate
+    let programs = state.config.programs().unwrap_or_default();
+    let commitments = &state.config.commitments;
+    let hello = HelloEvent {
+        version: env!("CARGO_PKG_VERSION"),
+        programs: programs
+            .iter()
+            .map(|program| HelloProgram {
+                source: &program.source,
+                program_id: &program.program_id,
+            })
+            .collect(),
+        commitment: commitments[0],
+        commitments,
+        latest_slot: state.latest_slot.load(Ordering::Relaxed),
+    };
+    let json = serde_json::to_string(&hello).unwrap_or_else(|_| "{}".to_string());
+    format!("event: hello\ndata: {}\n\n", json)
+}
+
+/// IP клиента для лимитов: первый адрес X-Forwarded-For, если ему доверяем, иначе адрес сокета
+fn client_ip(state: &SharedState, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    if state.config.trust_forwarded_for {
+        let forwarded = headers
+            .get("X-Forwarded-For")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.ip()
 }
 
-async fn health_handler() -> (StatusCode, &'static str) {
-    (StatusCode::OK, "OK")
+fn connection_refused(ip: IpAddr, limit: ConnectionLimit) -> Response {
+    match limit {
+        ConnectionLimit::PerIp => {
+            debug!("Отказ {}: превышен лимит подключений на IP", ip);
+            (StatusCode::TOO_MANY_REQUESTS, "Too many connections from this IP\n").into_response()
+        }
+        ConnectionLimit::Total => {
+            debug!("Отказ {}: превышен общий лимит подписчиков", ip);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [("Retry-After", SUBSCRIBERS_FULL_RETRY_AFTER_SECS.to_string())],
+                "Subscriber limit reached, retry later\n",
+            )
+                .into_response()
+        }
+    }
 }
 
-fn process_update(update: SubscribeUpdate) -> Option<CreateTransaction> {
-    if let Some(update_oneof) = update.update_oneof {
-        match update_oneof {
-            UpdateOneof::Transaction(tx_info) => {
-                // Проверяем, что это Create транзакция Pump.fun
-                if !is_pump_fun_create(&tx_info) {
-                    return None; // Пропускаем, если не Create
+/// То же, что /events, но для клиентов без EventSource: каждое событие - JSON text frame
+async fn ws_handler(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let ip = client_ip(&state, &headers, peer);
+    let guard = match ConnectionGuard::acquire(&state, ip) {
+        Ok(guard) => guard,
+        Err(limit) => return connection_refused(ip, limit),
+    };
+    let filter = EventFilter::from_query(query.as_deref());
+    ws.on_upgrade(move |socket| ws_session(socket, state, filter, guard))
+}
+
+/// `_guard` держит место в лимите IP до конца сессии
+async fn ws_session(mut socket: WebSocket, state: AppState, filter: EventFilter, _guard: ConnectionGuard) {
+    let mut rx = state.tx.subscribe();
+    loop {
+        tokio::select! {
+            result = rx.recv() => match result {
+                Ok(event) if !filter.matches(&event.create_tx) => {}
+                Ok(event) => {
+                    let Ok(json) = json::to_string(&event.create_tx, state.config.json_case) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("WebSocket клиент не успевает, пропущено {} событий", missed);
                 }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Ping(payload))) => {
+                    if socket.send(Message::Pong(payload)).await.is_err() {
+                        break;
+                    }
+                }
+                // Клиент закрыл соединение или оно оборвалось
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = state.shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+}
 
+fn sse_frame(event: &BroadcastEvent, case: JsonCase, fields: &FieldSet) -> Option<String> {
+    let json = if fields.is_all() {
+        json::to_string(&event.create_tx, case).ok()?
+    } else {
+        json::to_string(&fields.project(&event.create_tx).ok()?, case).ok()?
+    };
+    // Create остаются безымянными событиями (onmessage), миграции идут отдельным типом,
+    // чтобы не сломать клиентов, которые считают каждое сообщение созданием токена
+    match event.create_tx.event_type {
+        EventType::Create => Some(format!("id: {}\ndata: {}\n\n", event.id, json)),
+        EventType::Migrate => Some(format!("id: {}\nevent: migrate\ndata: {}\n\n", event.id, json)),
+        EventType::Account => Some(format!("id: {}\nevent: account\ndata: {}\n\n", event.id, json)),
+        EventType::UpstreamReconnect => Some(format!(
+            "id: {}\nevent: upstream_reconnect\ndata: {}\n\n",
+            event.id, json
+        )),
+    }
+}
+
+/// Клиент не успевал читать: сообщаем, сколько событий он пропустил
+fn lagged_frame(missed: u64) -> String {
+    format!("event: lagged\ndata: {{\"missed\":{}}}\n\n", missed)
+}
+
+/// Кадр с массивом событий; id последнего события, чтобы Last-Event-ID продолжал работать
+fn sse_batch_frame(
+    batch: &[Result<BroadcastEvent, BroadcastStreamRecvError>],
+    case: JsonCase,
+    fields: &FieldSet,
+) -> String {
+    let mut frames = String::new();
+    let mut events = Vec::with_capacity(batch.len());
+    for item in batch {
+        match item {
+            Ok(event) => events.push(event),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => frames.push_str(&lagged_frame(*missed)),
+        }
+    }
+    if let Some(last) = events.last() {
+        let payload: serde_json::Result<Vec<serde_json::Value>> =
+            events.iter().map(|event| fields.project(&event.create_tx)).collect();
+        if let Ok(json) = payload.and_then(|payload| json::to_string(&payload, case)) {
+            frames.push_str(&format!("id: {}\ndata: {}\n\n", last.id, json));
+        }
+    }
+    frames
+}
+
+/// `?name=1` или `?name=true`
+fn query_flag(query: Option<&str>, name: &str) -> bool {
+    query.is_some_and(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .any(|(key, value)| key == name && (value == "1" || value == "true"))
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentQuery {
+    limit: Option<usize>,
+}
+
+/// Бэкфилл для только что подключившихся клиентов, ключи в том же регистре, что и в /events
+async fn recent_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RecentQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let limit = query.limit.unwrap_or(state.config.recent_capacity as usize);
+    json::to_value(&state.recent(limit), state.config.json_case)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[derive(Debug, Serialize)]
+struct LeaderboardResponse {
+    window_secs: u64,
+    creators: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct LeaderboardEntry {
+    creator_address: String,
+    creates: usize,
+}
+
+/// Создатели с наибольшим числом Create за окно: серийные запуски и фабричные кошельки.
+/// `?limit=` вместо --leaderboard-size
+async fn leaderboard_handler(
+    State(state): State<AppState>,
+    Query(query): Query<RecentQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let limit = query.limit.unwrap_or(state.config.leaderboard_size);
+    let creators = state
+        .top_creators(limit)
+        .into_iter()
+        .map(|(creator_address, creates)| LeaderboardEntry { creator_address, creates })
+        .collect();
+    let response = LeaderboardResponse {
+        window_secs: state.config.leaderboard_window_secs,
+        creators,
+    };
+    json::to_value(&response, state.config.json_case)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Либо событие, либо причина отказа
+#[derive(Debug, Serialize)]
+struct DebugProcessResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<CreateTransaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rejected: Option<String>,
+}
+
+/// Диагностика "почему X не пришел": тело - base64 protobuf SubscribeUpdateTransaction
+/// (или SubscribeUpdate с транзакцией, как в --capture). Прогоняет детектор и фильтры
+/// без побочных эффектов: ни рассылки, ни отметки в dedup, ни счетчиков в /metrics
+async fn debug_process_handler(
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, message);
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, body.trim())
+        .map_err(|e| bad_request(format!("body is not base64: {}", e)))?;
+    let tx_info = decode_debug_transaction(&bytes).ok_or_else(|| {
+        bad_request("body is neither a SubscribeUpdateTransaction nor a transaction SubscribeUpdate".to_string())
+    })?;
+    let programs = state
+        .config
+        .programs()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let response = match process_transaction(&tx_info, &programs, &Metrics::default(), &[]) {
+        Some(create_tx) => DebugProcessResponse {
+            rejected: filtered_reason(&state, &create_tx),
+            event: Some(create_tx),
+        },
+        None => DebugProcessResponse {
+            event: None,
+            rejected: Some(rejection_reason(&tx_info, &programs)),
+        },
+    };
+    json::to_value(&response, state.config.json_case)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn decode_debug_transaction(bytes: &[u8]) -> Option<SubscribeUpdateTransaction> {
+    use yellowstone_grpc_proto::prost::Message as _;
+    // Транзакция без transaction тоже декодируется из чужих байт, поэтому сначала SubscribeUpdate
+    if let Ok(SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(tx_info)),
+        ..
+    }) = SubscribeUpdate::decode(bytes)
+    {
+        return Some(tx_info);
+    }
+    SubscribeUpdateTransaction::decode(bytes)
+        .ok()
+        .filter(|tx_info| tx_info.transaction.is_some())
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    /// Вершина цепи по slot подписке, для сравнения со slot событий
+    latest_slot: u64,
+    /// Попытки переподключения GRPC с запуска
+    grpc_reconnects: u64,
+    /// Последнее успешное подключение GRPC, unix секунды; null - еще не подключались
+    grpc_last_connect_at: Option<u64>,
+    /// Секунды с этого подключения: частые сбросы около нуля означают нестабильное соединение
+    grpc_connection_age_secs: Option<u64>,
+    /// Секунды с последнего Create; null - с запуска Create не было
+    last_create_secs_ago: Option<u64>,
+}
+
+/// Живость процесса; с --health-max-silence-secs еще и то, что Create действительно приходят
+async fn health_handler(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let since_last_create = state.since_last_create();
+    let max_silence = state.config.health_max_silence_secs;
+    let silence = since_last_create.unwrap_or_else(|| state.started_at.elapsed());
+    let (status_code, status) = if max_silence > 0 && silence.as_secs() >= max_silence {
+        (StatusCode::SERVICE_UNAVAILABLE, "SILENT")
+    } else {
+        (StatusCode::OK, "OK")
+    };
+    let response = HealthResponse {
+        status,
+        latest_slot: state.latest_slot.load(Ordering::Relaxed),
+        grpc_reconnects: state.metrics.grpc_reconnects.load(Ordering::Relaxed),
+        grpc_last_connect_at: Some(state.metrics.grpc_last_connect_secs.load(Ordering::Relaxed))
+            .filter(|at| *at > 0),
+        grpc_connection_age_secs: state.metrics.grpc_connection_age_secs(),
+        last_create_secs_ago: since_last_create.map(|since| since.as_secs()),
+    };
+    (status_code, Json(response))
+}
+
+#[derive(Debug, Serialize)]
+struct ReadyResponse {
+    status: &'static str,
+    grpc_connected: bool,
+}
+
+/// Readiness: 200 только пока GRPC подписка подключена, иначе события не придут.
+/// /health при этом остается проверкой живости процесса
+async fn ready_handler(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let connections = state.metrics.grpc_connections.load(Ordering::Relaxed);
+    let grpc_connected = connections >= state.config.commitments.len() as u64;
+    let (status_code, status) = if grpc_connected {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+    (status_code, Json(ReadyResponse { status, grpc_connected }))
+}
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    creates_total: u64,
+    creates_last_1m: usize,
+    creates_last_5m: usize,
+    creates_last_60m: usize,
+    sse_subscribers: usize,
+    /// Открытые SSE/WebSocket/TCP подключения, которые считаются в --max-subscribers
+    subscribers: usize,
+    /// 0 - без лимита
+    max_subscribers: usize,
+    uptime_secs: u64,
+}
+
+async fn stats_handler(State(state): State<AppState>) -> Json<StatsResponse> {
+    let windows = [60, 5 * 60, 60 * 60].map(tokio::time::Duration::from_secs);
+    let counts = state.creates_within(&windows);
+    Json(StatsResponse {
+        creates_total: state.metrics.creates_detected.load(Ordering::Relaxed),
+        creates_last_1m: counts[0],
+        creates_last_5m: counts[1],
+        creates_last_60m: counts[2],
+        sse_subscribers: state.receiver_count(),
+        subscribers: state.subscriber_count(),
+        max_subscribers: state.config.max_subscribers,
+        uptime_secs: state.started_at.elapsed().as_secs(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigResponse {
+    /// Все параметры под именами полей Config, секреты скрыты
+    config: serde_json::Value,
+    /// Основная программа и --program с итоговыми маркерами
+    programs: Vec<ProgramConfig>,
+    sinks: Vec<&'static str>,
+}
+
+/// Действующая конфигурация: параметры запуска, а LIVE_SETTINGS - с учетом перечитывания по SIGHUP
+async fn config_handler(State(state): State<AppState>) -> Json<ConfigResponse> {
+    let mut config = state.config.redacted();
+    if let serde_json::Value::Object(map) = &mut config {
+        let live = state.live.read().unwrap();
+        map.insert("min_dev_buy_lamports".to_string(), live.min_dev_buy_lamports.into());
+        let list = live.creator_list.as_ref();
+        for (key, kind) in [("creator_allowlist", "allowlist"), ("creator_denylist", "denylist")] {
+            let path = list
+                .filter(|list| list.kind() == kind)
+                .map(|list| list.path().display().to_string());
+            map.insert(key.to_string(), path.into());
+        }
+    }
+    Json(ConfigResponse {
+        config,
+        programs: state.config.programs().unwrap_or_default(),
+        sinks: state.config.enabled_sinks(),
+    })
+}
+
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [("Content-Type", "text/plain; version=0.0.4")],
+        state.metrics.render(state.receiver_count()),
+    )
+}
+
+/// `commitment` - уровень подписки, из которой пришло обновление (None для --replay).
+/// Спан process_update (см. --otlp-endpoint) покрывает всю обработку; emitted=false и dropped
+/// показывают, на каком шаге обновление отброшено
+fn process_update(
+    update: SubscribeUpdate,
+    programs: &[ProgramConfig],
+    state: &SharedState,
+    commitment: Option<Commitment>,
+) -> Option<CreateTransaction> {
+    let span = tracing::info_span!(
+        "process_update",
+        kind = update.update_oneof.as_ref().map_or("empty", update_kind),
+        commitment = commitment.map(Commitment::as_str),
+        slot = tracing::field::Empty,
+        signature = tracing::field::Empty,
+        event_type = tracing::field::Empty,
+        dropped = tracing::field::Empty,
+        emitted = false,
+    );
+    let _entered = span.enter();
+    let mut create_tx = match update.update_oneof {
+        Some(UpdateOneof::Transaction(tx_info)) => {
+            span.record("slot", tx_info.slot);
+            if !span.is_disabled() {
+                if let Some(signature) = tx_info.transaction.as_ref().map(|tx| &tx.signature) {
+                    span.record("signature", bs58::encode(signature).into_string());
+                }
+            }
+            let Some(mut create_tx) = process_transaction(&tx_info, programs, &state.metrics, &update.filters) else {
+                span.record("dropped", "not_detected");
+                return None;
+            };
+            span.record("event_type", create_tx.event_type.as_str());
+            if create_tx.slot < state.config.min_slot {
+                debug!(
+                    "Слот {} ниже --min-slot {}, пропускаем mint={}",
+                    create_tx.slot, state.config.min_slot, create_tx.mint_address
+                );
+                Metrics::inc(&state.metrics.below_min_slot);
+                span.record("dropped", "below_min_slot");
+                return None;
+            }
+            if let Some(reason) = filtered_reason(state, &create_tx) {
+                debug!("{}, пропускаем mint={}", reason, create_tx.mint_address);
+                Metrics::inc(&state.metrics.creates_filtered);
+                span.record("dropped", "filtered");
+                return None;
+            }
+            // После переподключения сервер может повторно прислать уже разосланные транзакции.
+            // Та же транзакция с другого commitment - не повтор, а ее подтверждение
+            let prefix = commitment.map_or(String::new(), |commitment| format!("{}:", commitment.as_str()));
+            if !state.seen_signatures.lock().unwrap().insert(&format!("{}{}", prefix, create_tx.signature)) {
+                debug!("Повтор подписи {}, пропускаем", create_tx.signature);
+                Metrics::inc(&state.metrics.duplicates_skipped);
+                span.record("dropped", "duplicate");
+                return None;
+            }
+            // Create и Migrate одного токена - разные события, поэтому ключ включает тип
+            let mint_key = format!("{}{}:{}", prefix, create_tx.event_type.as_str(), create_tx.mint_address);
+            if !state.seen_mints.lock().unwrap().insert(&mint_key) {
+                debug!(
+                    "Повтор mint {} ({}) в {}, пропускаем",
+                    create_tx.mint_address,
+                    create_tx.event_type.as_str(),
+                    create_tx.signature
+                );
+                Metrics::inc(&state.metrics.mint_duplicates_skipped);
+                span.record("dropped", "duplicate_mint");
+                return None;
+            }
+            create_tx.slot_lag = slot_lag(state, &create_tx);
+            if state.config.verbose_events {
                 if let Some(tx) = &tx_info.transaction {
-                    // Получаем подпись
-                    let signature = if !tx.signature.is_empty() {
-                        bs58::encode(&tx.signature).into_string()
-                    } else if let Some(tx_data) = &tx.transaction {
-                        if let Some(first_sig) = tx_data.signatures.first() {
-                            bs58::encode(first_sig).into_string()
-                        } else {
-                            return None;
-                        }
-                    } else {
-                        return None;
-                    };
+                    create_tx.account_keys = pump::account_keys(tx)
+                        .into_iter()
+                        .map(|key| bs58::encode(key).into_string())
+                        .collect();
+                }
+            }
+            Some(create_tx)
+        }
+        Some(UpdateOneof::Account(account_update)) => process_account(&account_update),
+        Some(UpdateOneof::Ping(_)) => {
+            debug!("🏓 GRPC ping от сервера");
+            None
+        }
+        Some(UpdateOneof::Pong(pong)) => {
+            debug!("🏓 GRPC pong id={}", pong.id);
+            None
+        }
+        Some(UpdateOneof::Slot(slot)) => {
+            trace!("GRPC slot={} status={}", slot.slot, slot.status);
+            state.latest_slot.fetch_max(slot.slot, Ordering::Relaxed);
+            None
+        }
+        Some(other) => {
+            trace!("Пропущено GRPC обновление: {}", update_kind(&other));
+            None
+        }
+        None => {
+            debug!("GRPC обновление без содержимого, filters={:?}", update.filters);
+            None
+        }
+    }?;
+    create_tx.commitment = commitment;
+    span.record("emitted", true);
+    Some(create_tx)
+}
 
-                    // Получаем creator (первый аккаунт)
-                    let creator_address = if let Some(tx_data) = &tx.transaction {
-                        if let Some(message) = &tx_data.message {
-                            if let Some(first_key) = message.account_keys.first() {
-                                bs58::encode(first_key).into_string()
-                            } else {
-                                return None;
-                            }
-                        } else {
-                            return None;
-                        }
-                    } else {
-                        return None;
-                    };
+/// Серверные фильтры Create для всех потребителей сразу; Some - причина отсева
+fn filtered_reason(state: &SharedState, create_tx: &CreateTransaction) -> Option<String> {
+    if create_tx.event_type != EventType::Create {
+        return None;
+    }
+    let live = state.live.read().unwrap();
+    // Запуски без покупки создателя - в основном спам
+    if create_tx.dev_buy_lamports < live.min_dev_buy_lamports {
+        return Some(format!(
+            "dev buy {} lamports is below --min-dev-buy-lamports {}",
+            create_tx.dev_buy_lamports, live.min_dev_buy_lamports
+        ));
+    }
+    let list = live.creator_list.as_ref()?;
+    (!list.allows(&create_tx.creator_address))
+        .then(|| format!("creator {} rejected by {}", create_tx.creator_address, list.kind()))
+}
 
-                    // Получаем mint из post_token_balances
-                    let mint_address = if let Some(meta) = &tx.meta {
-                        let post_balances = &meta.post_token_balances;
-                        let pre_balances = &meta.pre_token_balances;
-                        
-                        let pre_mints: std::collections::HashSet<String> = pre_balances.iter()
-                            .map(|b| b.mint.clone())
-                            .collect();
-                        
-                        let mut candidate_mints = vec![];
-                        for balance in post_balances {
-                            let mint = &balance.mint;
-                            if !pre_mints.contains(mint) && !mint.contains("11111111111111111111111111111111") {
-                                candidate_mints.push(mint.clone());
-                            }
-                        }
-                        
-                        candidate_mints.iter()
-                            .find(|m: &&String| m.ends_with("pump"))
-                            .or_else(|| candidate_mints.first())
-                            .cloned()
-                    } else {
-                        None
-                    };
+fn process_account(update: &SubscribeUpdateAccount) -> Option<CreateTransaction> {
+    let account = update.account.as_ref()?;
+    let pubkey = bs58::encode(&account.pubkey).into_string();
+    debug!("👁️ Account {} slot={} lamports={}", pubkey, update.slot, account.lamports);
+    Some(CreateTransaction {
+        event_type: EventType::Account,
+        source: "accounts".to_string(),
+        signature: account
+            .txn_signature
+            .as_ref()
+            .map(|signature| bs58::encode(signature).into_string())
+            .unwrap_or_default(),
+        slot: update.slot,
+        received_at: unix_millis(),
+        account: Some(AccountUpdate {
+            pubkey,
+            owner: bs58::encode(&account.owner).into_string(),
+            lamports: account.lamports,
+            data: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &account.data),
+            write_version: account.write_version,
+        }),
+        ..Default::default()
+    })
+}
 
-                    if let Some(mint) = mint_address {
-                        info!("🔥 Pump.fun Create: mint={} creator={} signature={}", mint, creator_address, signature);
-                        return Some(CreateTransaction {
-                            signature,
-                            mint_address: mint,
-                            creator_address,
-                            slot: tx_info.slot,
-                        });
-                    }
+/// Насколько событие отстает от вершины цепи; 0, пока слоты еще не приходили
+fn slot_lag(state: &SharedState, create_tx: &CreateTransaction) -> u64 {
+    let latest_slot = state.latest_slot.load(Ordering::Relaxed);
+    if latest_slot == 0 {
+        return 0;
+    }
+    let lag = latest_slot.saturating_sub(create_tx.slot);
+    state.metrics.last_slot_lag.store(lag, Ordering::Relaxed);
+    if lag > state.config.stale_slot_lag {
+        warn!(
+            mint = create_tx.mint_address.as_str(),
+            signature = create_tx.signature.as_str(),
+            slot = create_tx.slot,
+            slot_lag = lag;
+            "⚠️ Событие отстает от вершины на {} слотов (slot={} tip={}): возможно, данные отброшенного форка или эндпоинт деградировал, mint={}",
+            lag, create_tx.slot, latest_slot, create_tx.mint_address
+        );
+    }
+    lag
+}
+
+fn update_kind(update: &UpdateOneof) -> &'static str {
+    match update {
+        UpdateOneof::Account(_) => "account",
+        UpdateOneof::Slot(_) => "slot",
+        UpdateOneof::Transaction(_) => "transaction",
+        UpdateOneof::TransactionStatus(_) => "transaction_status",
+        UpdateOneof::Block(_) => "block",
+        UpdateOneof::Ping(_) => "ping",
+        UpdateOneof::Pong(_) => "pong",
+        UpdateOneof::BlockMeta(_) => "block_meta",
+        UpdateOneof::Entry(_) => "entry",
+    }
+}
+
+/// "source" или "source via filter" для лога: фильтр по умолчанию называется как source программы
+fn source_label(source: &str, filters: &str) -> String {
+    if filters.is_empty() || filters == source {
+        source.to_string()
+    } else {
+        format!("{} via {}", source, filters)
+    }
+}
+
+/// `filters` - имена фильтров подписки, по которым пришла транзакция (`SubscribeUpdate::filters`),
+/// только для лога; пусто у --debug/process и --signature
+fn process_transaction(
+    tx_info: &SubscribeUpdateTransaction,
+    programs: &[ProgramConfig],
+    metrics: &Metrics,
+    filters: &[String],
+) -> Option<CreateTransaction> {
+    let _span = tracing::debug_span!("detect").entered();
+    // Программа определяется по тому, чьи маркеры нашлись в логах
+    let Some((program, event_type, marker)) = programs
+        .iter()
+        .find_map(|program| {
+            detect_event(tx_info, program).map(|(event_type, marker)| (program, event_type, marker))
+        })
+    else {
+        Metrics::inc(&metrics.transactions_skipped);
+        return None; // Пропускаем, если не Create и не Migrate
+    };
+
+    if let Some(tx) = &tx_info.transaction {
+        // Получаем подпись
+        let signature = if !tx.signature.is_empty() {
+            bs58::encode(&tx.signature).into_string()
+        } else if let Some(first_sig) = tx.transaction.as_ref().and_then(|tx_data| tx_data.signatures.first()) {
+            bs58::encode(first_sig).into_string()
+        } else {
+            return partial_drop(metrics, program, "", Missing::Signature);
+        };
+
+        // Получаем creator (первый аккаунт); для миграции это подписант миграции
+        let Some(signer) = creator_key(tx) else {
+            let reason = match tx.transaction.as_ref().and_then(|tx_data| tx_data.message.as_ref()) {
+                Some(_) => Missing::AccountKeys,
+                None => Missing::Message,
+            };
+            return partial_drop(metrics, program, &signature, reason);
+        };
+        let creator_address = bs58::encode(signer).into_string();
+
+        // CreateEvent программы pump.fun точнее эвристик по балансам и ключам
+        let create_event = match event_type {
+            EventType::Create if program.is_pump_fun() => {
+                pump::parse_create_event(tx, &program.program_id, &program.program_pubkey)
+            }
+            _ => None,
+        };
+        let creator_address = create_event
+            .as_ref()
+            .map_or(creator_address, |event| bs58::encode(event.creator).into_string());
+        let is_fee_payer_creator = fee_payer(tx)
+            .is_some_and(|payer| bs58::encode(payer).into_string() == creator_address);
+
+        let mint_address = match (&tx.meta, event_type) {
+            (_, EventType::Create) if create_event.is_some() => {
+                create_event.as_ref().map(|event| bs58::encode(event.mint).into_string())
+            }
+            // Бывают Create с пустыми post_token_balances: тогда mint берем из аккаунтов инструкции
+            (Some(meta), EventType::Create) => created_mint(tx, meta).or_else(|| {
+                program
+                    .is_pump_fun()
+                    .then(|| pump::create_instruction_mint(tx, &program.program_pubkey))
+                    .flatten()
+            }),
+            (Some(meta), EventType::Migrate) => migrated_mint(meta),
+            // Из транзакций события account не получаются, они идут из account подписки
+            (_, EventType::Account | EventType::UpstreamReconnect) | (None, _) => None,
+        };
+
+        if let Some(mint) = mint_address {
+            let (metadata, dev_buy_lamports) = match event_type {
+                EventType::Create if program.is_pump_fun() => (
+                    create_event
+                        .as_ref()
+                        .map(|event| event.metadata.clone())
+                        .or_else(|| pump::parse_create_metadata(tx, &program.program_pubkey))
+                        .unwrap_or_default(),
+                    pump::dev_buy_lamports(tx, &program.program_pubkey, signer),
+                ),
+                _ => (TokenMetadata::default(), 0),
+            };
+            let bonding_curve = if let Some(event) = &create_event {
+                bs58::encode(event.bonding_curve).into_string()
+            } else if program.is_pump_fun() {
+                pump::bonding_curve_address(&mint, &program.program_pubkey).unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let bonding_curve_vault = if bonding_curve.is_empty() {
+                String::new()
+            } else {
+                pump::bonding_curve_vault(tx, &mint, &bonding_curve).unwrap_or_default()
+            };
+            let metadata_address = pump::metadata_address(&mint).unwrap_or_default();
+            let (initial_supply, decimals) = match (&tx.meta, event_type) {
+                (Some(meta), EventType::Create) => mint_supply(meta, &mint).unzip(),
+                _ => (None, None),
+            };
+            let matched = filters.join(",");
+            let label = source_label(&program.source, &matched);
+            match event_type {
+                EventType::Create => {
+                    info!(
+                        source = program.source.as_str(),
+                        filter = matched.as_str(),
+                        mint = mint.as_str(),
+                        creator = creator_address.as_str(),
+                        signature = signature.as_str(),
+                        slot = tx_info.slot;
+                        "🔥 Create [{}]: mint={} creator={} signature={}", label, mint, creator_address, signature
+                    );
+                    metrics.inc_create(&program.source);
+                }
+                EventType::Migrate => {
+                    info!(
+                        source = program.source.as_str(),
+                        filter = matched.as_str(),
+                        mint = mint.as_str(),
+                        signature = signature.as_str(),
+                        slot = tx_info.slot;
+                        "🎓 Migrate [{}]: mint={} signature={}", label, mint, signature
+                    );
+                    metrics.inc_migration(&program.source);
                 }
+                EventType::Account | EventType::UpstreamReconnect => {}
             }
-            _ => {}
+            return Some(CreateTransaction {
+                event_type,
+                source: program.source.clone(),
+                signature,
+                is_vanity: is_pump_vanity(&mint),
+                mint_address: mint,
+                creator_address,
+                is_fee_payer_creator,
+                slot: tx_info.slot,
+                slot_lag: 0,
+                commitment: None,
+                name: metadata.name,
+                symbol: metadata.symbol,
+                uri: metadata.uri,
+                bonding_curve,
+                bonding_curve_vault,
+                metadata_address,
+                instruction_variant: instruction_variant(marker),
+                dev_buy_lamports,
+                initial_supply,
+                decimals,
+                fee: tx.meta.as_ref().map_or(0, |meta| meta.fee),
+                compute_units_consumed: tx.meta.as_ref().and_then(|meta| meta.compute_units_consumed),
+                failed: is_failed(tx_info),
+                received_at: unix_millis(),
+                account_keys: Vec::new(),
+                account: None,
+                upstream: None,
+            });
         }
+        let reason = if tx.meta.is_some() { Missing::Mint } else { Missing::Meta };
+        return partial_drop(metrics, program, &signature, reason);
+    }
+    None
+}
+
+/// Supply и decimals mint по post_token_balances. В транзакции Create весь выпуск лежит на ее
+/// аккаунтах (bonding curve и покупка создателя), поэтому сумма балансов и есть supply.
+/// None, если балансов mint нет или они не разбираются
+fn mint_supply(meta: &TransactionStatusMeta, mint: &str) -> Option<(u64, u8)> {
+    let mut amounts = meta
+        .post_token_balances
+        .iter()
+        .filter(|balance| balance.mint == mint)
+        .filter_map(|balance| balance.ui_token_amount.as_ref())
+        .peekable();
+    let decimals = u8::try_from(amounts.peek()?.decimals).ok()?;
+    let mut supply: u64 = 0;
+    for amount in amounts {
+        supply = supply.checked_add(amount.amount.parse().ok()?)?;
     }
+    Some((supply, decimals))
+}
+
+/// Распознанное событие, из которого не собрать CreateTransaction: причина в debug лог и метрику
+fn partial_drop(
+    metrics: &Metrics,
+    program: &ProgramConfig,
+    signature: &str,
+    reason: Missing,
+) -> Option<CreateTransaction> {
+    debug!(
+        "⚠️ [{}] транзакция {} распознана, но пропущена: {}",
+        program.source,
+        if signature.is_empty() { "без подписи" } else { signature },
+        reason.as_str()
+    );
+    metrics.inc_partial_drop(reason);
     None
 }
 
-fn is_pump_fun_create(tx_info: &yellowstone_grpc_proto::prelude::SubscribeUpdateTransaction) -> bool {
-    let pump_fun_program_id = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+/// Первый аккаунт сообщения (подписант, платящий комиссию)
+fn creator_key(tx: &SubscribeUpdateTransactionInfo) -> Option<&[u8]> {
+    tx.transaction
+        .as_ref()?
+        .message
+        .as_ref()?
+        .account_keys
+        .first()
+        .map(|key| key.as_slice())
+}
+
+/// Плательщик комиссии по скомпилированному сообщению. Ключи в нем упорядочены:
+/// сначала `num_required_signatures` подписантов (из них последние `num_readonly_signed_accounts`
+/// только для чтения), потом остальные. Плательщик - всегда первый ключ, и рантайм требует,
+/// чтобы он был подписантом с правом записи (с него списывается комиссия). Проверяем это по
+/// заголовку: если подписантов нет или все они readonly, сообщение некорректно и плательщика нет
+fn fee_payer(tx: &SubscribeUpdateTransactionInfo) -> Option<&[u8]> {
+    let message = tx.transaction.as_ref()?.message.as_ref()?;
+    let header = message.header.as_ref()?;
+    if header.num_required_signatures == 0
+        || header.num_readonly_signed_accounts >= header.num_required_signatures
+    {
+        return None;
+    }
+    message.account_keys.first().map(|key| key.as_slice())
+}
+
+/// Новый mint: появился в post_token_balances, но не было в pre_token_balances.
+/// Из нескольких кандидатов предпочитаем vanity (`is_pump_vanity`). Если такого нет,
+/// ищем mint среди ключей (`new_signer_account`), и только потом берем первый кандидат,
+/// поэтому фильтр `?mint_suffix=pump` отсекает именно такие запасные варианты
+fn created_mint(tx: &SubscribeUpdateTransactionInfo, meta: &TransactionStatusMeta) -> Option<String> {
+    let post_balances = &meta.post_token_balances;
+    let pre_balances = &meta.pre_token_balances;
     
-    // Проверяем метаданные транзакции
-    if let Some(tx) = &tx_info.transaction {
-        if let Some(meta) = &tx.meta {
-            // Проверяем логи на наличие Pump.fun и Create
-            let log_messages = &meta.log_messages;
-            // log_messages это Vec<String>, нужно объединить в одну строку
-            let log_str = log_messages.join("\n");
-            
-            let has_pump_fun = log_str.contains(pump_fun_program_id);
-            let is_create = log_str.contains("Instruction: Create") && !log_str.contains("CreateV2");
-            let is_create_v2 = log_str.contains("Instruction: CreateV2");
-            
-            if has_pump_fun && (is_create || is_create_v2) {
-                return true;
-            }
+    let pre_mints: std::collections::HashSet<String> = pre_balances.iter()
+        .map(|b| b.mint.clone())
+        .collect();
+    
+    let mut candidate_mints = vec![];
+    for balance in post_balances {
+        let mint = &balance.mint;
+        if !pre_mints.contains(mint) && !mint.contains("11111111111111111111111111111111") {
+            candidate_mints.push(mint.clone());
         }
     }
     
-    false
-}
\ No newline at end of file
+    if let Some(mint) = candidate_mints.iter().find(|m| is_pump_vanity(m)) {
+        return Some(mint.clone());
+    }
+    new_signer_account(tx, meta).or_else(|| candidate_mints.first().cloned())
+}
+
+/// Mint в Create - новый аккаунт, подписанный своим (vanity) ключом. Он находится, даже если
+/// баланса по нему в post_token_balances нет или там первым стоит чужой mint: среди writable
+/// подписантов, кроме плательщика, ищем аккаунт с 0 лампортов до транзакции и ненулевым после
+fn new_signer_account(tx: &SubscribeUpdateTransactionInfo, meta: &TransactionStatusMeta) -> Option<String> {
+    let message = tx.transaction.as_ref()?.message.as_ref()?;
+    let header = message.header.as_ref()?;
+    let writable_signers = header
+        .num_required_signatures
+        .saturating_sub(header.num_readonly_signed_accounts) as usize;
+    let candidates: Vec<String> = message
+        .account_keys
+        .iter()
+        .enumerate()
+        .take(writable_signers)
+        .skip(1)
+        .filter(|(i, _)| {
+            meta.pre_balances.get(*i) == Some(&0) && meta.post_balances.get(*i).is_some_and(|b| *b > 0)
+        })
+        .map(|(_, key)| bs58::encode(key).into_string())
+        .collect();
+    candidates
+        .iter()
+        .find(|m| is_pump_vanity(m))
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+/// Мигрирующий mint уже существовал до транзакции (в отличие от нового LP mint пула)
+fn migrated_mint(meta: &TransactionStatusMeta) -> Option<String> {
+    let candidate_mints: Vec<&String> = meta
+        .pre_token_balances
+        .iter()
+        .map(|b| &b.mint)
+        .filter(|mint| !mint.contains("11111111111111111111111111111111"))
+        .collect();
+
+    candidate_mints
+        .iter()
+        .find(|m| is_pump_vanity(m))
+        .or_else(|| candidate_mints.first())
+        .map(|m| m.to_string())
+}
+
+/// --event-ttl-ms: проверка перед рассылкой. `created_at_ms` - время создания обновления
+/// на сервере, в возраст от него входит и очередь в GRPC потоке, а не только обработка
+fn is_stale(state: &SharedState, create_tx: &CreateTransaction, created_at_ms: Option<u64>) -> bool {
+    let ttl = state.config.event_ttl_ms;
+    if ttl == 0 {
+        return false;
+    }
+    let age = unix_millis().saturating_sub(created_at_ms.unwrap_or(create_tx.received_at));
+    if age <= ttl {
+        return false;
+    }
+    debug!(
+        mint = create_tx.mint_address.as_str(),
+        signature = create_tx.signature.as_str(),
+        age_ms = age;
+        "⌛ Событие mint={} устарело: {}ms > --event-ttl-ms {}, не рассылаем",
+        create_tx.mint_address, age, ttl
+    );
+    Metrics::inc(&state.metrics.stale_dropped);
+    true
+}
+
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Миграция проверяется первой: ее логи содержат "Instruction: CreatePool" от AMM,
+/// который иначе совпал бы с маркером Create. Возвращает тип события и совпавший маркер
+fn detect_event<'a>(
+    tx_info: &SubscribeUpdateTransaction,
+    program: &'a ProgramConfig,
+) -> Option<(EventType, &'a str)> {
+    // Подписка уже просит failed: false, но логи упавшей транзакции тоже содержат маркеры,
+    // поэтому проверяем ошибку сами, не полагаясь на семантику фильтра эндпоинта
+    if !program.include_failed && is_failed(tx_info) {
+        return None;
+    }
+    if let Some(marker) = matched_migrate_marker(tx_info, program) {
+        Some((EventType::Migrate, marker))
+    } else {
+        matched_create_marker(tx_info, program).map(|marker| (EventType::Create, marker))
+    }
+}
+
+fn is_failed(tx_info: &SubscribeUpdateTransaction) -> bool {
+    tx_info
+        .transaction
+        .as_ref()
+        .and_then(|tx| tx.meta.as_ref())
+        .is_some_and(|meta| meta.err.is_some())
+}
+
+fn matched_create_marker<'a>(
+    tx_info: &SubscribeUpdateTransaction,
+    program: &'a ProgramConfig,
+) -> Option<&'a str> {
+    let meta = tx_info.transaction.as_ref()?.meta.as_ref()?;
+    program_logged_marker(&meta.log_messages, &program.program_id, &program.create_markers)
+}
+
+fn matched_migrate_marker<'a>(
+    tx_info: &SubscribeUpdateTransaction,
+    program: &'a ProgramConfig,
+) -> Option<&'a str> {
+    let meta = tx_info.transaction.as_ref()?.meta.as_ref()?;
+    program_logged_marker(&meta.log_messages, &program.program_id, &program.migrate_markers)
+}
+
+/// Маркер, который вывела сама программа (см. `logs::program_lines`), чтобы маркер
+/// вложенной программы (например "Instruction: CreatePool" у AMM) не засчитывался вызывающей.
+/// Из совпавших в строке берем самый длинный: "Instruction: Create" входит в "Instruction: CreateV2"
+fn program_logged_marker<'a>(
+    log_messages: &[String],
+    program_id: &str,
+    markers: &'a [String],
+) -> Option<&'a str> {
+    for line in logs::program_lines(log_messages, program_id) {
+        let matched = markers
+            .iter()
+            .filter(|marker| line.contains(marker.as_str()))
+            .max_by_key(|marker| marker.len());
+        if let Some(marker) = matched {
+            return Some(marker);
+        }
+    }
+    None
+}
+
+/// "Instruction: CreateV2" -> "create_v2": имя инструкции из маркера в snake_case
+fn instruction_variant(marker: &str) -> String {
+    let name = marker.strip_prefix("Instruction: ").unwrap_or(marker).trim();
+    let mut variant = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                variant.push('_');
+            }
+            variant.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            variant.push(c);
+            prev_lower = true;
+        } else if !variant.ends_with('_') {
+            variant.push('_');
+            prev_lower = false;
+        }
+    }
+    variant
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use solana_pubkey::Pubkey;
+    use yellowstone_grpc_proto::prelude::{
+        CompiledInstruction, Message, MessageAddressTableLookup, MessageHeader, TokenBalance, Transaction,
+        UiTokenAmount,
+    };
+
+    use super::*;
+
+    const MINT: &str = "ALUBc9eMdvmD8feiER8do6e5hFZmcVsNXfXRMHtDpump";
+    const CREATOR: &str = "7Vbmv1jt4vyuqBZcpYPpnVhrqVe5e6ZPb6JxDcffRHUM";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qMi1Hw6PeHcXw6a2Ln1Kvcy6gw";
+    const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+    const TOKEN_METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+    fn key(address: &str) -> Pubkey {
+        address.parse().unwrap()
+    }
+
+    fn pda(seeds: &[&[u8]], program: &str) -> Pubkey {
+        Pubkey::find_program_address(seeds, &key(program)).0
+    }
+
+    fn balance(account_index: u32, mint: &str, owner: &Pubkey) -> TokenBalance {
+        TokenBalance {
+            account_index,
+            mint: mint.to_string(),
+            owner: owner.to_string(),
+            program_id: TOKEN_PROGRAM.to_string(),
+            ui_token_amount: Some(UiTokenAmount {
+                amount: "1000000000000000".to_string(),
+                decimals: 6,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Create в той форме, в какой pump.fun транзакции приходят из Geyser: подписанты - создатель и
+    /// новый mint, затем writable аккаунты, которые создает Create (bonding curve, ее ATA, metadata),
+    /// затем программы и PDA pump.fun. Лампорты - rent новых аккаунтов, весь выпуск лежит на ATA
+    /// bonding curve. Mint и создатель выдуманы, остальные адреса настоящие
+    fn pump_create() -> (SubscribeUpdateTransactionInfo, TransactionStatusMeta) {
+        let mint = key(MINT);
+        let bonding_curve = pda(&[b"bonding-curve", mint.as_ref()], config::PUMP_FUN_PROGRAM_ID);
+        let vault = pda(
+            &[bonding_curve.as_ref(), key(TOKEN_PROGRAM).as_ref(), mint.as_ref()],
+            ASSOCIATED_TOKEN_PROGRAM,
+        );
+        let metadata = pda(
+            &[b"metadata", key(TOKEN_METADATA_PROGRAM).as_ref(), mint.as_ref()],
+            TOKEN_METADATA_PROGRAM,
+        );
+        let account_keys = [
+            key(CREATOR),
+            mint,
+            bonding_curve,
+            vault,
+            metadata,
+            key("ComputeBudget111111111111111111111111111111"),
+            key("11111111111111111111111111111111"),
+            key(TOKEN_PROGRAM),
+            key(ASSOCIATED_TOKEN_PROGRAM),
+            key("SysvarRent111111111111111111111111111111111"),
+            key(TOKEN_METADATA_PROGRAM),
+            pda(&[b"mint-authority"], config::PUMP_FUN_PROGRAM_ID),
+            pda(&[b"global"], config::PUMP_FUN_PROGRAM_ID),
+            key(config::PUMP_FUN_PROGRAM_ID),
+            pda(&[b"__event_authority"], config::PUMP_FUN_PROGRAM_ID),
+        ];
+        let tx = SubscribeUpdateTransactionInfo {
+            signature: vec![7; 64],
+            transaction: Some(Transaction {
+                signatures: vec![vec![7; 64], vec![8; 64]],
+                message: Some(Message {
+                    header: Some(MessageHeader {
+                        num_required_signatures: 2,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 10,
+                    }),
+                    account_keys: account_keys.iter().map(|key| key.to_bytes().to_vec()).collect(),
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        };
+        let mut pre_balances = vec![2_500_000_000, 0, 0, 0, 0];
+        pre_balances.extend([1; 10]);
+        let mut post_balances = vec![2_476_497_480, 1_461_600, 1_231_920, 2_039_280, 15_616_720];
+        post_balances.extend([1; 10]);
+        let meta = TransactionStatusMeta {
+            fee: 10_000,
+            pre_balances,
+            post_balances,
+            post_token_balances: vec![balance(3, MINT, &bonding_curve)],
+            ..Default::default()
+        };
+        (tx, meta)
+    }
+
+    #[test]
+    fn created_mint_of_pump_create() {
+        let (tx, meta) = pump_create();
+        assert_eq!(created_mint(&tx, &meta).as_deref(), Some(MINT));
+        assert_eq!(new_signer_account(&tx, &meta).as_deref(), Some(MINT));
+    }
+
+    #[test]
+    fn created_mint_without_mint_balance_uses_new_signer() {
+        // Баланс mint не пришел, а первым новым mint в балансах стоит чужой токен
+        // (например, бандлер в той же транзакции продает USDC)
+        let (tx, mut meta) = pump_create();
+        meta.post_token_balances = vec![balance(3, USDC, &key(CREATOR))];
+        assert_eq!(created_mint(&tx, &meta).as_deref(), Some(MINT));
+
+        // Баланс mint нулевой и стоит не первым
+        meta.post_token_balances.push(TokenBalance {
+            ui_token_amount: None,
+            ..balance(3, MINT, &key(CREATOR))
+        });
+        assert_eq!(created_mint(&tx, &meta).as_deref(), Some(MINT));
+
+        meta.post_token_balances.clear();
+        assert_eq!(created_mint(&tx, &meta).as_deref(), Some(MINT));
+    }
+
+    #[test]
+    fn new_signer_account_skips_payer_and_readonly_signers() {
+        let (mut tx, mut meta) = pump_create();
+        meta.post_token_balances.clear();
+        // Плательщик без лампортов до транзакции кандидатом не становится
+        meta.pre_balances[0] = 0;
+        assert_eq!(new_signer_account(&tx, &meta).as_deref(), Some(MINT));
+        // Подписант только для чтения не может быть новым аккаунтом
+        let message = tx.transaction.as_mut().unwrap().message.as_mut().unwrap();
+        message.header.as_mut().unwrap().num_readonly_signed_accounts = 1;
+        assert_eq!(new_signer_account(&tx, &meta), None);
+        assert_eq!(created_mint(&tx, &meta), None);
+    }
+
+    #[test]
+    fn created_mint_in_v0_transaction_with_lookup_table() {
+        // v0 бандлера: в сообщении остаются подписанты, bonding curve и pump.fun, а ATA bonding curve
+        // (writable) и остальные программы загружаются из lookup table. Балансы индексируются по
+        // полному списку ключей: статичные, затем загруженные writable и readonly
+        let (mut tx, legacy) = pump_create();
+        let message = tx.transaction.as_mut().unwrap().message.as_mut().unwrap();
+        let keys = std::mem::take(&mut message.account_keys);
+        message.account_keys = vec![keys[0].clone(), keys[1].clone(), keys[2].clone(), keys[13].clone()];
+        message.header = Some(MessageHeader {
+            num_required_signatures: 2,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        });
+        message.versioned = true;
+        message.address_table_lookups = vec![MessageAddressTableLookup {
+            account_key: vec![42; 32],
+            writable_indexes: vec![0, 1],
+            readonly_indexes: (2..11).collect(),
+        }];
+        let order = [0, 1, 2, 13, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 14];
+        let meta = TransactionStatusMeta {
+            pre_balances: order.iter().map(|&i| legacy.pre_balances[i]).collect(),
+            post_balances: order.iter().map(|&i| legacy.post_balances[i]).collect(),
+            post_token_balances: vec![TokenBalance {
+                account_index: 4,
+                ..legacy.post_token_balances[0].clone()
+            }],
+            loaded_writable_addresses: vec![keys[3].clone(), keys[4].clone()],
+            loaded_readonly_addresses: [5, 6, 7, 8, 9, 10, 11, 12, 14].iter().map(|&i| keys[i].clone()).collect(),
+            ..legacy
+        };
+        assert_eq!(created_mint(&tx, &meta).as_deref(), Some(MINT));
+        // Новые writable аккаунты из lookup table подписантами не бывают и mint не считаются
+        assert_eq!(new_signer_account(&tx, &meta).as_deref(), Some(MINT));
+        let mut without_mint = meta.clone();
+        without_mint.post_token_balances.clear();
+        without_mint.post_balances[1] = 0;
+        assert_eq!(new_signer_account(&tx, &without_mint), None);
+
+        tx.meta = Some(meta);
+        let all_keys = pump::account_keys(&tx);
+        assert_eq!(all_keys.len(), 15);
+        assert_eq!(all_keys[4], keys[3].as_slice());
+    }
+
+    #[test]
+    fn pump_vanity_requires_exact_suffix_and_pubkey() {
+        assert!(is_pump_vanity(MINT));
+        assert!(is_pump_vanity("2eBc8Rh36yFhSBiJQgRY99xZXYYM7B8qPqJYwnCbpump"));
+        // Регистр суффикса важен
+        assert!(!is_pump_vanity("A4boH9CJ5DEMiMbrFLWamDKQu3J423padEaYHW8kPUMP"));
+        assert!(!is_pump_vanity("A4boH9CJ5DEMiMbrFLWamDKQu3J423padEaYHW8kPump"));
+        // Короче 43 символов, включая ключи короче самого суффикса
+        assert!(!is_pump_vanity(""));
+        assert!(!is_pump_vanity("ump"));
+        assert!(!is_pump_vanity("pump"));
+        assert!(!is_pump_vanity("So1pump"));
+        // Нужная длина, но не base58 ('0') или больше 32 байт
+        assert!(!is_pump_vanity("ALUBc9eMdvmD8feiER8do6e5hFZmcVsNXfXRMHt0pump"));
+        assert!(!is_pump_vanity("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzpump"));
+    }
+
+    #[test]
+    fn created_mint_prefers_only_real_vanity() {
+        // Лампорты нового подписанта не приходят, mint выбирается только по балансам
+        let (tx, mut meta) = pump_create();
+        meta.post_balances[1] = 0;
+        let with_mints = |mints: &[&str]| TransactionStatusMeta {
+            post_token_balances: mints.iter().map(|mint| balance(3, mint, &key(CREATOR))).collect(),
+            ..meta.clone()
+        };
+        assert_eq!(created_mint(&tx, &with_mints(&[USDC, MINT])).as_deref(), Some(MINT));
+        // "PUMP" и мусор на "pump" не вытесняют обычный mint, и он не теряется
+        let lookalikes = ["A4boH9CJ5DEMiMbrFLWamDKQu3J423padEaYHW8kPUMP", "zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzpump"];
+        assert_eq!(created_mint(&tx, &with_mints(&[USDC, lookalikes[0], lookalikes[1]])).as_deref(), Some(USDC));
+        assert_eq!(created_mint(&tx, &with_mints(&lookalikes)).as_deref(), Some(lookalikes[0]));
+    }
+
+    /// Дискриминатор Create, sha256("global:create")[..8]; за ним идут borsh name, symbol, uri, creator
+    const CREATE_IX: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+
+    #[test]
+    fn stale_events_are_dropped_by_server_time_first() {
+        let state = |ttl: &str| {
+            let config = Config::parse_from(["solana-geyser-test", "--event-ttl-ms", ttl]);
+            SharedState::new(Arc::new(config), broadcast::channel(1).0)
+        };
+        let now = unix_millis();
+        let fresh = CreateTransaction {
+            received_at: now,
+            ..Default::default()
+        };
+        let old = CreateTransaction {
+            received_at: now - 60_000,
+            ..Default::default()
+        };
+
+        let off = state("0");
+        assert!(!is_stale(&off, &old, Some(now - 60_000)));
+
+        let state = state("5000");
+        assert!(!is_stale(&state, &fresh, None));
+        assert!(is_stale(&state, &old, None));
+        // created_at сервера важнее received_at: событие долго стояло в очереди GRPC потока
+        assert!(is_stale(&state, &fresh, Some(now - 60_000)));
+        assert!(!is_stale(&state, &old, Some(now)));
+        assert_eq!(state.metrics.stale_dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn source_label_names_filter_only_when_it_differs() {
+        assert_eq!(source_label("pump_fun", ""), "pump_fun");
+        assert_eq!(source_label("pump_fun", "pump_fun"), "pump_fun");
+        assert_eq!(source_label("pump_fun", "bots"), "pump_fun via bots");
+        assert_eq!(source_label("pump_fun", "bots,all"), "pump_fun via bots,all");
+    }
+
+    #[test]
+    fn create_without_token_balances_takes_mint_from_instruction() {
+        let (mut tx, mut meta) = pump_create();
+        // Ни балансов токенов, ни лампортов: эвристики mint не находят
+        meta.post_token_balances.clear();
+        meta.pre_balances.clear();
+        meta.post_balances.clear();
+        meta.log_messages = vec![
+            format!("Program {} invoke [1]", config::PUMP_FUN_PROGRAM_ID),
+            "Program log: Instruction: Create".to_string(),
+            format!("Program {} success", config::PUMP_FUN_PROGRAM_ID),
+        ];
+        assert_eq!(created_mint(&tx, &meta), None);
+
+        let mut data = CREATE_IX.to_vec();
+        data.extend(borsh::to_vec(&("Geyser Test", "GYSR", "https://x/y.json", key(CREATOR).to_bytes())).unwrap());
+        // Аккаунты Create по IDL pump.fun: mint, mint_authority, bonding_curve, vault, global,
+        // mpl_token_metadata, metadata, user, system, token, associated token, rent, event_authority, program
+        let message = tx.transaction.as_mut().unwrap().message.as_mut().unwrap();
+        message.instructions = vec![CompiledInstruction {
+            program_id_index: 13,
+            accounts: vec![1, 11, 2, 3, 12, 10, 4, 0, 6, 7, 8, 9, 14, 13],
+            data,
+        }];
+        tx.meta = Some(meta);
+        let update = SubscribeUpdateTransaction {
+            transaction: Some(tx),
+            slot: 1,
+        };
+        let config = Config::parse_from(["solana-geyser-test"]);
+        let event = process_transaction(&update, &config.programs().unwrap(), &Metrics::default(), &[]).unwrap();
+        assert_eq!(event.event_type, EventType::Create);
+        assert_eq!(event.mint_address, MINT);
+        assert_eq!(event.creator_address, CREATOR);
+        assert_eq!(event.name, "Geyser Test");
+        assert_eq!(event.symbol, "GYSR");
+    }
+}
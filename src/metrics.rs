@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Create/Migrate одного источника (program source)
+#[derive(Debug, Default, Clone, Copy)]
+struct SourceCounts {
+    creates: u64,
+    migrations: u64,
+}
+
+/// Чего не хватило в обновлении, чтобы распознанный Create/Migrate стал событием
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    Signature,
+    Message,
+    AccountKeys,
+    Meta,
+    Mint,
+}
+
+impl Missing {
+    pub const ALL: [Missing; 5] = [
+        Missing::Signature,
+        Missing::Message,
+        Missing::AccountKeys,
+        Missing::Meta,
+        Missing::Mint,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Missing::Signature => "no_signature",
+            Missing::Message => "no_message",
+            Missing::AccountKeys => "no_account_keys",
+            Missing::Meta => "no_meta",
+            Missing::Mint => "no_mint",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Counter,
+    Gauge,
+}
+
+/// Одно значение метрики в момент снятия
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub help: &'static str,
+    pub value: u64,
+}
+
+/// Счетчики для /metrics (Prometheus text format)
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub updates_received: AtomicU64,
+    pub creates_detected: AtomicU64,
+    pub migrations_detected: AtomicU64,
+    pub transactions_skipped: AtomicU64,
+    pub duplicates_skipped: AtomicU64,
+    pub mint_duplicates_skipped: AtomicU64,
+    pub creates_filtered: AtomicU64,
+    pub below_min_slot: AtomicU64,
+    pub stale_dropped: AtomicU64,
+    pub broadcast_no_subscribers: AtomicU64,
+    pub webhook_deliveries: AtomicU64,
+    pub webhook_failures: AtomicU64,
+    pub sqlite_writes: AtomicU64,
+    pub sqlite_failures: AtomicU64,
+    pub file_writes: AtomicU64,
+    pub file_failures: AtomicU64,
+    pub redis_publishes: AtomicU64,
+    pub redis_failures: AtomicU64,
+    pub nats_publishes: AtomicU64,
+    pub nats_failures: AtomicU64,
+    pub kafka_publishes: AtomicU64,
+    pub kafka_failures: AtomicU64,
+    pub grpc_reconnects: AtomicU64,
+    /// Подключенные GRPC подписки (по одной на --commitment)
+    pub grpc_connections: AtomicU64,
+    /// Время последнего успешного подключения GRPC (любой подписки), unix секунды; 0 - еще не было
+    pub grpc_last_connect_secs: AtomicU64,
+    /// Отставание последнего события от вершины цепи, слоты
+    pub last_slot_lag: AtomicU64,
+    /// По причинам Missing, в порядке Missing::ALL
+    partial_drops: [AtomicU64; Missing::ALL.len()],
+    /// Счетчики по источникам, чтобы строить график каждого лаунчпада отдельно
+    by_source: Mutex<BTreeMap<String, SourceCounts>>,
+}
+
+impl Metrics {
+    pub fn inc(counter: &AtomicU64) {
+        Self::add(counter, 1);
+    }
+
+    pub fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Заводит нулевые счетчики источников, чтобы серии были в /metrics до первого события
+    pub fn register_sources<'a>(&self, sources: impl IntoIterator<Item = &'a str>) {
+        let mut by_source = self.by_source.lock().unwrap();
+        for source in sources {
+            by_source.entry(source.to_string()).or_default();
+        }
+    }
+
+    pub fn inc_create(&self, source: &str) {
+        Self::inc(&self.creates_detected);
+        self.by_source.lock().unwrap().entry(source.to_string()).or_default().creates += 1;
+    }
+
+    pub fn inc_partial_drop(&self, reason: Missing) {
+        Self::inc(&self.partial_drops[reason as usize]);
+    }
+
+    pub fn inc_migration(&self, source: &str) {
+        Self::inc(&self.migrations_detected);
+        self.by_source.lock().unwrap().entry(source.to_string()).or_default().migrations += 1;
+    }
+
+    /// Учитывает подписку в grpc_connections, пока guard жив
+    pub fn grpc_connection(&self) -> GrpcConnection<'_> {
+        Self::inc(&self.grpc_connections);
+        self.grpc_last_connect_secs.store(unix_secs(), Ordering::Relaxed);
+        GrpcConnection { metrics: self }
+    }
+
+    /// Секунды с последнего успешного подключения GRPC, None - подключений еще не было.
+    /// Маленькое значение при идущих событиях означает, что соединение переподключается
+    pub fn grpc_connection_age_secs(&self) -> Option<u64> {
+        match self.grpc_last_connect_secs.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(unix_secs().saturating_sub(at)),
+        }
+    }
+
+    /// Все метрики без меток; их же отправляет statsd sink, поэтому значения совпадают с /metrics
+    pub fn samples(&self, sse_subscribers: usize) -> Vec<Sample> {
+        vec![
+            Sample {
+                name: "geyser_updates_received_total",
+                kind: Kind::Counter,
+                help: "Updates received from the gRPC stream",
+                value: self.updates_received.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_creates_detected_total",
+                kind: Kind::Counter,
+                help: "Create transactions detected",
+                value: self.creates_detected.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_migrations_detected_total",
+                kind: Kind::Counter,
+                help: "Migrate (bonding curve graduation) transactions detected",
+                value: self.migrations_detected.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_transactions_skipped_total",
+                kind: Kind::Counter,
+                help: "Transactions skipped as not matching the create markers",
+                value: self.transactions_skipped.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_duplicates_skipped_total",
+                kind: Kind::Counter,
+                help: "Events skipped because the signature was already emitted",
+                value: self.duplicates_skipped.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_mint_duplicates_skipped_total",
+                kind: Kind::Counter,
+                help: "Events skipped because the mint was already emitted (--mint-dedup-capacity)",
+                value: self.mint_duplicates_skipped.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_creates_filtered_total",
+                kind: Kind::Counter,
+                help: "Creates dropped by server-side filters (min dev buy, creator allow/deny list)",
+                value: self.creates_filtered.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_below_min_slot_total",
+                kind: Kind::Counter,
+                help: "Creates and migrations dropped because their slot is below --min-slot",
+                value: self.below_min_slot.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_stale_dropped_total",
+                kind: Kind::Counter,
+                help: "Events not broadcast because they were older than --event-ttl-ms",
+                value: self.stale_dropped.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_broadcast_no_subscribers_total",
+                kind: Kind::Counter,
+                help: "Events published while no SSE client or sink was subscribed",
+                value: self.broadcast_no_subscribers.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_webhook_deliveries_total",
+                kind: Kind::Counter,
+                help: "Events delivered to webhooks",
+                value: self.webhook_deliveries.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_webhook_failures_total",
+                kind: Kind::Counter,
+                help: "Webhook deliveries given up after retries or dropped due to lag",
+                value: self.webhook_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_sqlite_writes_total",
+                kind: Kind::Counter,
+                help: "Events written to SQLite (including ignored duplicates)",
+                value: self.sqlite_writes.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_sqlite_failures_total",
+                kind: Kind::Counter,
+                help: "SQLite writes that failed or were dropped due to lag",
+                value: self.sqlite_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_file_writes_total",
+                kind: Kind::Counter,
+                help: "Events appended to the JSONL output file",
+                value: self.file_writes.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_file_failures_total",
+                kind: Kind::Counter,
+                help: "JSONL writes that failed or were dropped due to lag",
+                value: self.file_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_redis_publishes_total",
+                kind: Kind::Counter,
+                help: "Events published to the Redis channel",
+                value: self.redis_publishes.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_redis_failures_total",
+                kind: Kind::Counter,
+                help: "Redis publishes that failed or were dropped due to lag",
+                value: self.redis_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_nats_publishes_total",
+                kind: Kind::Counter,
+                help: "Events published to NATS",
+                value: self.nats_publishes.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_nats_failures_total",
+                kind: Kind::Counter,
+                help: "NATS publishes that failed or were dropped due to lag",
+                value: self.nats_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_kafka_publishes_total",
+                kind: Kind::Counter,
+                help: "Events acknowledged by Kafka",
+                value: self.kafka_publishes.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_kafka_failures_total",
+                kind: Kind::Counter,
+                help: "Kafka deliveries that failed after retries or were dropped due to lag",
+                value: self.kafka_failures.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_grpc_reconnects_total",
+                kind: Kind::Counter,
+                help: "gRPC subscription reconnect attempts",
+                value: self.grpc_reconnects.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_grpc_connected",
+                kind: Kind::Gauge,
+                help: "gRPC subscriptions currently connected (one per commitment)",
+                value: self.grpc_connections.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_grpc_last_connect_timestamp_seconds",
+                kind: Kind::Gauge,
+                help: "Unix time of the last successful gRPC connect, 0 if never connected",
+                value: self.grpc_last_connect_secs.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_grpc_connection_age_seconds",
+                kind: Kind::Gauge,
+                help: "Seconds since the last successful gRPC (re)connect, 0 if never connected",
+                value: self.grpc_connection_age_secs().unwrap_or(0),
+            },
+            Sample {
+                name: "geyser_slot_lag",
+                kind: Kind::Gauge,
+                help: "Slots between the chain tip and the last emitted event",
+                value: self.last_slot_lag.load(Ordering::Relaxed),
+            },
+            Sample {
+                name: "geyser_sse_subscribers",
+                kind: Kind::Gauge,
+                help: "Currently connected SSE subscribers",
+                value: sse_subscribers as u64,
+            },
+        ]
+    }
+
+    /// Create/Migrate по источникам, от меньшего имени к большему
+    pub fn source_counts(&self) -> Vec<(String, u64, u64)> {
+        self.by_source
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, counts)| (source.clone(), counts.creates, counts.migrations))
+            .collect()
+    }
+
+    /// Рендерит все метрики; число SSE подписчиков берется из broadcast канала
+    pub fn render(&self, sse_subscribers: usize) -> String {
+        let mut out = String::new();
+        for sample in &self.samples(sse_subscribers) {
+            write_metric(&mut out, sample);
+        }
+        let by_source = self.source_counts();
+        write_labeled(
+            &mut out,
+            "geyser_source_creates_total",
+            "Create transactions detected, by program source",
+            "source",
+            by_source.iter().map(|(source, creates, _)| (source.as_str(), *creates)),
+        );
+        write_labeled(
+            &mut out,
+            "geyser_source_migrations_total",
+            "Migrate transactions detected, by program source",
+            "source",
+            by_source.iter().map(|(source, _, migrations)| (source.as_str(), *migrations)),
+        );
+        write_labeled(
+            &mut out,
+            "geyser_partial_drops_total",
+            "Detected creates/migrations dropped because the update lacked data, by missing part",
+            "reason",
+            Missing::ALL
+                .iter()
+                .map(|reason| (reason.as_str(), self.partial_drops[*reason as usize].load(Ordering::Relaxed))),
+        );
+        out
+    }
+}
+
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Подключенная GRPC подписка: снимается с учета при drop (обрыв, ошибка или остановка)
+pub struct GrpcConnection<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for GrpcConnection<'_> {
+    fn drop(&mut self) {
+        self.metrics.grpc_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn write_metric(out: &mut String, sample: &Sample) {
+    let kind = match sample.kind {
+        Kind::Counter => "counter",
+        Kind::Gauge => "gauge",
+    };
+    let _ = writeln!(out, "# HELP {} {}", sample.name, sample.help);
+    let _ = writeln!(out, "# TYPE {} {}", sample.name, kind);
+    let _ = writeln!(out, "{} {}", sample.name, sample.value);
+}
+
+/// Counter с одной меткой: строка на каждое значение метки
+fn write_labeled<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: impl Iterator<Item = (&'a str, u64)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (value_label, value) in values {
+        let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, escape_label(value_label), value);
+    }
+}
+
+/// Экранирование значения метки по формату Prometheus
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
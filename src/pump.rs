@@ -0,0 +1,469 @@
+use base64::Engine;
+use borsh::BorshDeserialize;
+use solana_pubkey::Pubkey;
+use yellowstone_grpc_proto::prelude::SubscribeUpdateTransactionInfo;
+
+use crate::logs;
+
+/// Anchor дискриминаторы: первые 8 байт sha256("global:<имя инструкции>")
+const CREATE_DISCRIMINATOR: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+const CREATE_V2_DISCRIMINATOR: [u8; 8] = [214, 144, 76, 236, 95, 139, 49, 180];
+
+/// Anchor `emit_cpi!`: событие приходит вложенной инструкцией к самой программе,
+/// данные начинаются с EVENT_IX_TAG (u64 0x1d9acb512ea545e4 в little-endian)
+const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+/// Первые 8 байт sha256("event:TradeEvent")
+const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+/// Associated Token Account программа и классический SPL Token
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const TOKEN_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+/// Metaplex Token Metadata
+const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Первые 8 байт sha256("event:CreateEvent")
+const CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+/// Anchor `emit!`: событие в логах программы строкой "Program data: <base64>"
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Начало аргументов Create и CreateV2: дальше у версий идут разные поля,
+/// поэтому читаем только общий префикс
+#[derive(BorshDeserialize)]
+struct CreateArgs {
+    name: String,
+    symbol: String,
+    uri: String,
+}
+
+/// Начало TradeEvent, остальные поля не нужны
+#[derive(BorshDeserialize)]
+struct TradeEvent {
+    _mint: [u8; 32],
+    sol_amount: u64,
+    _token_amount: u64,
+    is_buy: bool,
+    user: [u8; 32],
+}
+
+/// Начало CreateEvent. В новых версиях после `user` идет `creator`,
+/// в старых его нет, поэтому он читается отдельно
+#[derive(BorshDeserialize)]
+struct CreateEventFields {
+    name: String,
+    symbol: String,
+    uri: String,
+    mint: [u8; 32],
+    bonding_curve: [u8; 32],
+    user: [u8; 32],
+}
+
+/// CreateEvent программы: то, что она сама записала о созданном токене
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateEvent {
+    pub mint: [u8; 32],
+    pub bonding_curve: [u8; 32],
+    pub creator: [u8; 32],
+    pub metadata: TokenMetadata,
+}
+
+/// Метаданные токена из данных Create инструкции
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Инструкция транзакции: индекс программы, индексы аккаунтов в account_keys и данные
+pub struct Instruction<'a> {
+    pub program_id_index: u32,
+    pub accounts: &'a [u8],
+    pub data: &'a [u8],
+}
+
+/// Все ключи транзакции в порядке индексации инструкций:
+/// статичные из сообщения, затем загруженные через lookup tables (writable, readonly)
+pub fn account_keys(tx: &SubscribeUpdateTransactionInfo) -> Vec<&[u8]> {
+    let mut keys: Vec<&[u8]> = Vec::new();
+    if let Some(message) = tx.transaction.as_ref().and_then(|t| t.message.as_ref()) {
+        keys.extend(message.account_keys.iter().map(|k| k.as_slice()));
+    }
+    if let Some(meta) = &tx.meta {
+        keys.extend(meta.loaded_writable_addresses.iter().map(|k| k.as_slice()));
+        keys.extend(meta.loaded_readonly_addresses.iter().map(|k| k.as_slice()));
+    }
+    keys
+}
+
+/// Инструкции верхнего уровня, затем вложенные (CPI, например из бандлеров)
+pub fn instructions(tx: &SubscribeUpdateTransactionInfo) -> Vec<Instruction<'_>> {
+    let mut result = Vec::new();
+    if let Some(message) = tx.transaction.as_ref().and_then(|t| t.message.as_ref()) {
+        result.extend(message.instructions.iter().map(|ix| Instruction {
+            program_id_index: ix.program_id_index,
+            accounts: &ix.accounts,
+            data: &ix.data,
+        }));
+    }
+    if let Some(meta) = &tx.meta {
+        for inner in &meta.inner_instructions {
+            result.extend(inner.instructions.iter().map(|ix| Instruction {
+                program_id_index: ix.program_id_index,
+                accounts: &ix.accounts,
+                data: &ix.data,
+            }));
+        }
+    }
+    result
+}
+
+/// Находит Create/CreateV2 инструкцию программы и декодирует имя, символ и URI
+pub fn parse_create_metadata(
+    tx: &SubscribeUpdateTransactionInfo,
+    program_id: &[u8; 32],
+) -> Option<TokenMetadata> {
+    let keys = account_keys(tx);
+    instructions(tx)
+        .into_iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program_id.as_slice()))
+        .find_map(|ix| decode_create_args(ix.data))
+}
+
+/// Mint из аккаунтов Create/CreateV2 инструкции: у обеих версий он первый аккаунт.
+/// Запасной вариант, когда нет ни CreateEvent, ни новых token balances
+pub fn create_instruction_mint(tx: &SubscribeUpdateTransactionInfo, program_id: &[u8; 32]) -> Option<String> {
+    let keys = account_keys(tx);
+    instructions(tx)
+        .into_iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program_id.as_slice()))
+        .filter(|ix| {
+            ix.data.get(..8).is_some_and(|discriminator| {
+                discriminator == CREATE_DISCRIMINATOR || discriminator == CREATE_V2_DISCRIMINATOR
+            })
+        })
+        .find_map(|ix| keys.get(*ix.accounts.first()? as usize))
+        .map(|mint| bs58::encode(mint).into_string())
+}
+
+fn decode_create_args(data: &[u8]) -> Option<TokenMetadata> {
+    let (discriminator, mut args) = data.split_at_checked(8)?;
+    if discriminator != CREATE_DISCRIMINATOR && discriminator != CREATE_V2_DISCRIMINATOR {
+        return None;
+    }
+    let args = CreateArgs::deserialize(&mut args).ok()?;
+    Some(TokenMetadata {
+        name: args.name,
+        symbol: args.symbol,
+        uri: args.uri,
+    })
+}
+
+/// CreateEvent из логов программы ("Program data:") или, если его там нет,
+/// из вложенной self-CPI инструкции `emit_cpi!`
+pub fn parse_create_event(
+    tx: &SubscribeUpdateTransactionInfo,
+    program_id: &str,
+    program_pubkey: &[u8; 32],
+) -> Option<CreateEvent> {
+    let from_logs = tx.meta.as_ref().and_then(|meta| {
+        logs::program_lines(&meta.log_messages, program_id)
+            .into_iter()
+            .filter_map(|line| line.strip_prefix(PROGRAM_DATA_PREFIX))
+            .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+            .find_map(|data| {
+                let (discriminator, fields) = data.split_at_checked(8)?;
+                decode_create_event(discriminator, fields)
+            })
+    });
+    from_logs.or_else(|| {
+        let keys = account_keys(tx);
+        instructions(tx)
+            .into_iter()
+            .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program_pubkey.as_slice()))
+            .find_map(|ix| {
+                let (tag, rest) = ix.data.split_at_checked(8)?;
+                let (discriminator, fields) = rest.split_at_checked(8)?;
+                if tag != EVENT_IX_TAG {
+                    return None;
+                }
+                decode_create_event(discriminator, fields)
+            })
+    })
+}
+
+fn decode_create_event(discriminator: &[u8], mut fields: &[u8]) -> Option<CreateEvent> {
+    if discriminator != CREATE_EVENT_DISCRIMINATOR {
+        return None;
+    }
+    let event = CreateEventFields::deserialize(&mut fields).ok()?;
+    let creator = <[u8; 32]>::deserialize(&mut fields).unwrap_or(event.user);
+    Some(CreateEvent {
+        mint: event.mint,
+        bonding_curve: event.bonding_curve,
+        creator,
+        metadata: TokenMetadata {
+            name: event.name,
+            symbol: event.symbol,
+            uri: event.uri,
+        },
+    })
+}
+
+/// Сколько лампортов `buyer` потратил на покупки в этой транзакции (по TradeEvent)
+pub fn dev_buy_lamports(
+    tx: &SubscribeUpdateTransactionInfo,
+    program_id: &[u8; 32],
+    buyer: &[u8],
+) -> u64 {
+    let keys = account_keys(tx);
+    instructions(tx)
+        .into_iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program_id.as_slice()))
+        .filter_map(|ix| decode_trade_event(ix.data))
+        .filter(|event| event.is_buy && event.user.as_slice() == buyer)
+        .map(|event| event.sol_amount)
+        .sum()
+}
+
+fn decode_trade_event(data: &[u8]) -> Option<TradeEvent> {
+    let (tag, rest) = data.split_at_checked(8)?;
+    let (discriminator, mut fields) = rest.split_at_checked(8)?;
+    if tag != EVENT_IX_TAG || discriminator != TRADE_EVENT_DISCRIMINATOR {
+        return None;
+    }
+    TradeEvent::deserialize(&mut fields).ok()
+}
+
+/// Адрес bonding curve токена: PDA программы с seeds ["bonding-curve", mint].
+/// Выводим адрес, а не ищем среди account_keys: позиция аккаунта отличается у Create/CreateV2
+/// и у обёрток-бандлеров, а деривация однозначна и не зависит от формы транзакции.
+pub fn bonding_curve_address(mint: &str, program_id: &[u8; 32]) -> Option<String> {
+    let mint = bs58::decode(mint).into_vec().ok()?;
+    let program_id = Pubkey::new_from_array(*program_id);
+    let (address, _bump) =
+        Pubkey::try_find_program_address(&[b"bonding-curve", mint.as_slice()], &program_id)?;
+    Some(address.to_string())
+}
+
+/// Metaplex metadata аккаунт mint: PDA Token Metadata с seeds ["metadata", program id, mint].
+/// Create передает его в инструкцию; у CreateV2 (Token-2022) метаданные лежат в самом mint,
+/// и по этому адресу аккаунта нет.
+pub fn metadata_address(mint: &str) -> Option<String> {
+    let mint = mint.parse::<Pubkey>().ok()?;
+    let (address, _bump) = Pubkey::try_find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )?;
+    Some(address.to_string())
+}
+
+/// Vault bonding curve: associated token account, в котором лежат токены кривой.
+/// ATA - PDA программы ATokenGP... с seeds [владелец, token program, mint]. Token program у
+/// Create (SPL Token) и CreateV2 (Token-2022) разная, поэтому сначала берем ее из
+/// post_token_balances этого mint, затем пробуем SPL Token и каждый ключ транзакции:
+/// верным считается вариант, чей адрес есть среди account_keys (vault передается в Create).
+/// Если совпадения нет, возвращаем деривацию с программой из балансов или SPL Token.
+pub fn bonding_curve_vault(
+    tx: &SubscribeUpdateTransactionInfo,
+    mint_address: &str,
+    bonding_curve: &str,
+) -> Option<String> {
+    let mint = mint_address.parse::<Pubkey>().ok()?;
+    let owner = bonding_curve.parse::<Pubkey>().ok()?;
+    let hint = tx.meta.as_ref().and_then(|meta| {
+        meta.post_token_balances
+            .iter()
+            .find(|balance| balance.mint == mint_address && !balance.program_id.is_empty())
+            .and_then(|balance| balance.program_id.parse::<Pubkey>().ok())
+    });
+    let default = hint.unwrap_or(TOKEN_PROGRAM_ID);
+    let keys = account_keys(tx);
+    let candidates = hint
+        .into_iter()
+        .chain(Some(TOKEN_PROGRAM_ID))
+        .chain(keys.iter().filter_map(|key| <[u8; 32]>::try_from(*key).ok().map(Pubkey::new_from_array)));
+    for token_program in candidates {
+        let Some(vault) = associated_token_address(&owner, &token_program, &mint) else {
+            continue;
+        };
+        if keys.contains(&vault.as_ref()) {
+            return Some(vault.to_string());
+        }
+    }
+    associated_token_address(&owner, &default, &mint).map(|vault| vault.to_string())
+}
+
+fn associated_token_address(owner: &Pubkey, token_program: &Pubkey, mint: &Pubkey) -> Option<Pubkey> {
+    let (address, _bump) = Pubkey::try_find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )?;
+    Some(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::prelude::{
+        InnerInstruction, InnerInstructions, Message, Transaction, TransactionStatusMeta,
+    };
+
+    use super::*;
+    use crate::config::PUMP_FUN_PROGRAM_ID;
+
+    const MINT: &str = "ALUBc9eMdvmD8feiER8do6e5hFZmcVsNXfXRMHtDpump";
+    const CREATOR: &str = "7Vbmv1jt4vyuqBZcpYPpnVhrqVe5e6ZPb6JxDcffRHUM";
+    const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const METADATA: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+    const SYSTEM: &str = "11111111111111111111111111111111";
+    /// CreateEvent новой версии: после `user` идут `creator`, timestamp и резервы кривой
+    const CREATE_EVENT: &str = "Program data: G3KpTd7rY3YLAAAAR2V5c2VyIFRlc3QEAAAAR1lTUkMAAABodHRwczovL2lwZnMuaW8vaXBmcy9RbVl3QVBKenY1Q1pzbkE2MjVzM1hmMm5lbXRZZ1BwSGRXRXo3OW9qV25QYmRHirX0Fp1g2HPzp37AQnndh4jZmXMgxkw0JZTNVe0coG/WeJcM8PMDanobgA66USikNv/P2ORcEWyKlN5SDzYPlWB5tCeNI5hVgEaTfIDEc/FWn8+UJ+qJVrIE7jBmsoASYHm0J40jmFWARpN8gMRz8Vafz5Qn6olWsgTuMGaygBIAeOdoAAAAAAAQ2EfjzwMAAKwj/AYAAAAAeMX7UdECAACAxqR+jQMA";
+    /// Конец поля `user`: в старых версиях CreateEvent на нем заканчивался
+    const USER_END: usize = 8 + 15 + 8 + 71 + 32 * 3;
+
+    fn pump_pubkey() -> [u8; 32] {
+        PUMP_FUN_PROGRAM_ID.parse::<Pubkey>().unwrap().to_bytes()
+    }
+
+    fn bytes(address: &str) -> [u8; 32] {
+        address.parse::<Pubkey>().unwrap().to_bytes()
+    }
+
+    fn event_data() -> Vec<u8> {
+        let data = CREATE_EVENT.strip_prefix(PROGRAM_DATA_PREFIX).unwrap();
+        base64::engine::general_purpose::STANDARD.decode(data).unwrap()
+    }
+
+    fn program_data(data: &[u8]) -> String {
+        format!("{}{}", PROGRAM_DATA_PREFIX, base64::engine::general_purpose::STANDARD.encode(data))
+    }
+
+    /// Логи Create в том порядке, в каком их пишет рантайм: вложенные вызовы System, Token,
+    /// ATA и Metaplex, затем событие самой pump.fun. `event` - строка "Program data:" или пусто
+    fn create_logs(event: Option<String>) -> Vec<String> {
+        let pump = PUMP_FUN_PROGRAM_ID;
+        let mut logs: Vec<String> = [
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_string(),
+            "Program ComputeBudget111111111111111111111111111111 success".to_string(),
+            format!("Program {} invoke [1]", pump),
+            "Program log: Instruction: Create".to_string(),
+            format!("Program {} invoke [2]", SYSTEM),
+            format!("Program {} success", SYSTEM),
+            format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: InitializeMint2".to_string(),
+            format!("Program {} consumed 2780 of 221182 compute units", TOKEN),
+            format!("Program {} success", TOKEN),
+            format!("Program {} invoke [2]", METADATA),
+            "Program log: IX: Create Metadata Accounts v3".to_string(),
+            format!("Program {} invoke [3]", SYSTEM),
+            format!("Program {} success", SYSTEM),
+            format!("Program {} consumed 34355 of 196011 compute units", METADATA),
+            format!("Program {} success", METADATA),
+            format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: MintTo".to_string(),
+            format!("Program {} success", TOKEN),
+        ]
+        .into();
+        logs.extend(event);
+        logs.push(format!("Program {} consumed 119402 of 249700 compute units", pump));
+        logs.push(format!("Program {} success", pump));
+        logs
+    }
+
+    fn tx_with_logs(log_messages: Vec<String>) -> SubscribeUpdateTransactionInfo {
+        SubscribeUpdateTransactionInfo {
+            meta: Some(TransactionStatusMeta {
+                log_messages,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn parse(tx: &SubscribeUpdateTransactionInfo) -> Option<CreateEvent> {
+        parse_create_event(tx, PUMP_FUN_PROGRAM_ID, &pump_pubkey())
+    }
+
+    #[test]
+    fn create_event_from_program_data_log() {
+        let event = parse(&tx_with_logs(create_logs(Some(CREATE_EVENT.to_string())))).unwrap();
+        assert_eq!(event.metadata.name, "Geyser Test");
+        assert_eq!(event.metadata.symbol, "GYSR");
+        assert_eq!(event.metadata.uri, "https://ipfs.io/ipfs/QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG");
+        assert_eq!(event.mint, bytes(MINT));
+        let bonding_curve = bonding_curve_address(MINT, &pump_pubkey()).unwrap();
+        assert_eq!(event.bonding_curve, bytes(&bonding_curve));
+        assert_eq!(event.creator, bytes(CREATOR));
+    }
+
+    #[test]
+    fn create_event_without_creator_uses_user() {
+        let old = program_data(&event_data()[..USER_END]);
+        assert_eq!(parse(&tx_with_logs(create_logs(Some(old)))).unwrap().creator, bytes(CREATOR));
+    }
+
+    #[test]
+    fn create_event_rejects_wrong_discriminator_and_truncated_payload() {
+        let mut wrong = event_data();
+        wrong[0] ^= 1;
+        assert_eq!(parse(&tx_with_logs(create_logs(Some(program_data(&wrong))))), None);
+        // Обрываем внутри `bonding_curve`, и совсем до полей
+        for len in [USER_END - 48, 4] {
+            let truncated = program_data(&event_data()[..len]);
+            assert_eq!(parse(&tx_with_logs(create_logs(Some(truncated)))), None, "{}", len);
+        }
+        assert_eq!(parse(&tx_with_logs(create_logs(Some("Program data: не base64".to_string())))), None);
+        assert_eq!(parse(&tx_with_logs(create_logs(None))), None);
+    }
+
+    #[test]
+    fn create_event_ignores_nested_program_logs() {
+        // Та же строка, но ее вывела вложенная в pump.fun программа
+        let mut logs = create_logs(None);
+        let metaplex_end = logs.iter().position(|line| line.contains("34355")).unwrap();
+        logs.insert(metaplex_end, CREATE_EVENT.to_string());
+        assert_eq!(parse(&tx_with_logs(logs)), None);
+    }
+
+    #[test]
+    fn create_event_falls_back_to_emit_cpi_instruction() {
+        // Логи обрезаны рантаймом, событие остается во вложенной self-CPI инструкции
+        let mut data = EVENT_IX_TAG.to_vec();
+        data.extend(event_data());
+        let tx = SubscribeUpdateTransactionInfo {
+            transaction: Some(Transaction {
+                message: Some(Message {
+                    account_keys: vec![bytes(CREATOR).to_vec(), pump_pubkey().to_vec()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            meta: Some(TransactionStatusMeta {
+                log_messages: vec!["Log truncated".to_string()],
+                inner_instructions: vec![InnerInstructions {
+                    index: 0,
+                    instructions: vec![InnerInstruction {
+                        program_id_index: 1,
+                        data,
+                        ..Default::default()
+                    }],
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let event = parse(&tx).unwrap();
+        assert_eq!(event.mint, bytes(MINT));
+        assert_eq!(event.creator, bytes(CREATOR));
+    }
+
+    #[test]
+    fn metadata_address_matches_known_pda() {
+        // Metaplex metadata аккаунт USDC
+        assert_eq!(
+            metadata_address("EPjFWdd5AufqSSqeM2qMi1Hw6PeHcXw6a2Ln1Kvcy6gw").as_deref(),
+            Some("CDoEYiTZ33PSaqasTJYiMxAd3eX38cgMghBNxGfjNk9Y")
+        );
+        assert_eq!(metadata_address("not a pubkey"), None);
+    }
+}
@@ -0,0 +1,77 @@
+//! Офлайн прогон сохраненных GRPC обновлений через тот же путь детекции, что и живая подписка.
+//! Формат файла: подряд записи "длина (u32 big-endian) + SubscribeUpdate в protobuf".
+
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use tokio::sync::broadcast;
+use yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::prelude::SubscribeUpdate;
+use yellowstone_grpc_proto::prost::Message;
+
+use crate::config::Config;
+use crate::state::SharedState;
+
+/// Записи больше этого считаем испорченным файлом, а не огромным обновлением
+const MAX_RECORD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Печатает события в stdout по строке JSON; отвергнутые транзакции - в debug лог с причиной
+pub fn run(config: Arc<Config>, path: &Path) -> Result<()> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let programs = config.programs()?;
+    let case = config.json_case;
+    let (tx, _) = broadcast::channel(1);
+    let state = SharedState::new(config, tx);
+    crate::apply_live_settings(&state, &state.config)?;
+
+    let mut updates = 0u64;
+    let mut events = 0u64;
+    while let Some(update) = read_update(&mut reader)
+        .with_context(|| format!("Invalid record #{} in {}", updates + 1, path.display()))?
+    {
+        updates += 1;
+        let transaction = match &update.update_oneof {
+            Some(UpdateOneof::Transaction(tx_info)) => Some(tx_info.clone()),
+            _ => None,
+        };
+        match crate::process_update(update, &programs, &state, None) {
+            Some(create_tx) => {
+                events += 1;
+                println!("{}", crate::json::to_string(&create_tx, case)?);
+            }
+            None => {
+                if let Some(tx_info) = transaction.filter(|_| log::log_enabled!(log::Level::Debug)) {
+                    let signature = tx_info
+                        .transaction
+                        .as_ref()
+                        .map(|tx| bs58::encode(&tx.signature).into_string())
+                        .unwrap_or_default();
+                    debug!("Rejected {}: {}", signature, crate::rejection_reason(&tx_info, &programs));
+                }
+            }
+        }
+    }
+    info!("🔁 Replay {}: обновлений {}, событий {}", path.display(), updates, events);
+    Ok(())
+}
+
+/// None - файл закончился ровно на границе записи
+fn read_update(reader: &mut impl Read) -> Result<Option<SubscribeUpdate>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_RECORD_BYTES {
+        anyhow::bail!("record length {} exceeds {} bytes", len, MAX_RECORD_BYTES);
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).context("truncated record")?;
+    Ok(Some(SubscribeUpdate::decode(buf.as_slice())?))
+}
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use log::{info, warn};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::JsonCase;
+use crate::metrics::Metrics;
+use crate::state::AppState;
+use crate::CreateTransaction;
+
+/// JSONL файл с ротацией по размеру: при переполнении file -> file.1 -> file.2 ...
+struct RotatingWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: u32,
+    written: u64,
+    writer: BufWriter<File>,
+}
+
+/// Открывает файл на старте (ошибка пути видна сразу) и запускает задачу записи
+pub async fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(path) = &state.config.output_file else {
+        return Ok(());
+    };
+    let writer = RotatingWriter::open(
+        path.clone(),
+        state.config.output_max_bytes,
+        state.config.output_keep,
+    )
+    .await?;
+    info!("📝 Запись событий в JSONL: {}", path.display());
+    tokio::spawn(run(state.clone(), writer));
+    Ok(())
+}
+
+async fn run(state: AppState, mut writer: RotatingWriter) {
+    let mut rx = state.tx.subscribe();
+    // Буфер сбрасываем по таймеру: при падении теряется не больше одного интервала
+    let period = Duration::from_millis(state.config.output_flush_ms);
+    let mut flush = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = flush.tick() => {
+                if let Err(e) = writer.writer.flush().await {
+                    warn!("❌ Не удалось сбросить {}: {}", writer.path.display(), e);
+                }
+                continue;
+            }
+            _ = state.shutdown.cancelled() => break,
+        };
+        match event {
+            Ok(event) => match writer.write(&event.create_tx, state.config.json_case).await {
+                Ok(()) => Metrics::inc(&state.metrics.file_writes),
+                Err(e) => {
+                    warn!("❌ Ошибка записи в {}: {:#}", writer.path.display(), e);
+                    Metrics::inc(&state.metrics.file_failures);
+                }
+            },
+            Err(RecvError::Lagged(missed)) => {
+                warn!("JSONL запись не успевает, пропущено {} событий", missed);
+                Metrics::add(&state.metrics.file_failures, missed);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+    if let Err(e) = writer.writer.flush().await {
+        warn!("❌ Не удалось сбросить {}: {}", writer.path.display(), e);
+    }
+}
+
+impl RotatingWriter {
+    async fn open(path: PathBuf, max_bytes: u64, keep: u32) -> anyhow::Result<Self> {
+        let file = open_append(&path).await?;
+        let written = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            max_bytes,
+            keep,
+            written,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    async fn write(&mut self, create_tx: &CreateTransaction, case: JsonCase) -> anyhow::Result<()> {
+        let mut line = crate::json::to_vec(create_tx, case)?;
+        line.push(b'\n');
+        if self.max_bytes > 0 && self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate().await?;
+        }
+        self.writer.write_all(&line).await?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Сдвигает file.N-1 -> file.N, ..., file -> file.1; самый старый файл перезаписывается
+    async fn rotate(&mut self) -> anyhow::Result<()> {
+        self.writer.flush().await?;
+        if self.keep == 0 {
+            tokio::fs::remove_file(&self.path).await?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, n);
+                if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                    tokio::fs::rename(&from, rotated_path(&self.path, n + 1)).await?;
+                }
+            }
+            tokio::fs::rename(&self.path, rotated_path(&self.path, 1)).await?;
+        }
+        self.writer = BufWriter::new(open_append(&self.path).await?);
+        self.written = 0;
+        info!("📝 Ротация {}", self.path.display());
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open output file {}", path.display()))
+}
+
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use log::{info, warn};
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::metrics::Metrics;
+use crate::state::AppState;
+
+/// Пауза перед повторной постановкой в очередь, если локальная очередь librdkafka заполнена
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Повторы при недоступности брокеров делает сам librdkafka в пределах --kafka-message-timeout-ms,
+/// задача только ставит сообщения в его очередь и не ждет подтверждений по одному
+pub fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(brokers) = &state.config.kafka_brokers else {
+        return Ok(());
+    };
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set("message.timeout.ms", state.config.kafka_message_timeout_ms.to_string())
+        .create()
+        .map_err(|e| anyhow::anyhow!("Failed to create Kafka producer: {}", e))?;
+    info!("📮 Публикация событий в Kafka топик {} ({})", state.config.kafka_topic, brokers);
+    tokio::spawn(run(state.clone(), producer));
+    Ok(())
+}
+
+async fn run(state: AppState, producer: FutureProducer) {
+    let mut rx = state.tx.subscribe();
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = state.shutdown.cancelled() => break,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Kafka не успевает, пропущено {} событий", missed);
+                Metrics::add(&state.metrics.kafka_failures, missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let payload = match crate::json::to_vec(&event.create_tx, state.config.json_case) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Не удалось сериализовать событие для Kafka: {}", e);
+                Metrics::inc(&state.metrics.kafka_failures);
+                continue;
+            }
+        };
+        // Ключ mint держит события одного токена в одной партиции, по порядку
+        let mint = event.create_tx.mint_address.as_str();
+        let mut record = FutureRecord::to(&state.config.kafka_topic).payload(&payload);
+        if !mint.is_empty() {
+            record = record.key(mint);
+        }
+        loop {
+            match producer.send_result(record) {
+                Ok(delivery) => {
+                    let state = state.clone();
+                    let mint = mint.to_string();
+                    tokio::spawn(async move {
+                        match delivery.await {
+                            Ok(Ok(_)) => Metrics::inc(&state.metrics.kafka_publishes),
+                            Ok(Err((e, _))) => {
+                                warn!("❌ Kafka не доставил событие mint={}: {}", mint, e);
+                                Metrics::inc(&state.metrics.kafka_failures);
+                            }
+                            Err(_) => Metrics::inc(&state.metrics.kafka_failures),
+                        }
+                    });
+                    break;
+                }
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    record = returned;
+                    tokio::select! {
+                        _ = tokio::time::sleep(QUEUE_FULL_BACKOFF) => {}
+                        _ = state.shutdown.cancelled() => break,
+                    }
+                }
+                Err((e, _)) => {
+                    warn!("❌ Kafka ошибка публикации: mint={} {}", mint, e);
+                    Metrics::inc(&state.metrics.kafka_failures);
+                    break;
+                }
+            }
+        }
+    }
+    // Дожидаемся доставки того, что еще в очереди librdkafka
+    let timeout = Duration::from_millis(state.config.kafka_message_timeout_ms);
+    if let Err(e) = tokio::task::spawn_blocking(move || producer.flush(timeout)).await {
+        warn!("Kafka flush не завершился: {}", e);
+    }
+}
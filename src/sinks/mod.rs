@@ -0,0 +1,12 @@
+//! Доставка событий во внешние системы. Каждый sink - отдельная задача со своей
+//! подпиской на broadcast канал, так что медленный получатель не тормозит SSE и другие sinks.
+
+pub mod file;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod nats;
+pub mod redis;
+pub mod sqlite;
+pub mod statsd;
+pub mod tcp;
+pub mod webhook;
@@ -0,0 +1,83 @@
+use log::{debug, info, warn};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::metrics::Metrics;
+use crate::state::AppState;
+use crate::CreateTransaction;
+
+/// Клиент async-nats сам переподключается и буферизует публикации на время разрыва,
+/// поэтому задача только подключается и следит за событиями соединения
+pub fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(url) = state.config.nats_url.clone() else {
+        return Ok(());
+    };
+    info!("📮 Публикация событий в NATS subject {}", state.config.nats_subject);
+    tokio::spawn(run(state.clone(), url));
+    Ok(())
+}
+
+async fn run(state: AppState, url: String) {
+    let options = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .event_callback(|event| async move {
+            match event {
+                async_nats::Event::Connected => info!("✅ NATS подключен"),
+                async_nats::Event::Disconnected => warn!("NATS соединение потеряно, переподключение..."),
+                other => debug!("NATS: {}", other),
+            }
+        });
+    let client = tokio::select! {
+        result = options.connect(url.as_str()) => result,
+        _ = state.shutdown.cancelled() => return,
+    };
+    let client = match client {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("❌ NATS не подключен: {}", e);
+            return;
+        }
+    };
+
+    let mut rx = state.tx.subscribe();
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = state.shutdown.cancelled() => break,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("NATS не успевает, пропущено {} событий", missed);
+                Metrics::add(&state.metrics.nats_failures, missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let payload = match crate::json::to_vec(&event.create_tx, state.config.json_case) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Не удалось сериализовать событие для NATS: {}", e);
+                Metrics::inc(&state.metrics.nats_failures);
+                continue;
+            }
+        };
+        match client.publish(subject(&state, &event.create_tx), payload.into()).await {
+            Ok(()) => Metrics::inc(&state.metrics.nats_publishes),
+            Err(e) => {
+                warn!("❌ NATS ошибка публикации: mint={} {}", event.create_tx.mint_address, e);
+                Metrics::inc(&state.metrics.nats_failures);
+            }
+        }
+    }
+    let _ = client.flush().await;
+}
+
+/// С --nats-subject-mint подписчики могут фильтровать по mint: pumpfun.create.<mint>
+fn subject(state: &AppState, create_tx: &CreateTransaction) -> String {
+    // У событий account mint нет, пустой токен subject NATS не примет
+    if state.config.nats_subject_mint && !create_tx.mint_address.is_empty() {
+        format!("{}.{}", state.config.nats_subject, create_tx.mint_address)
+    } else {
+        state.config.nats_subject.clone()
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use log::{debug, error, info, warn};
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::metrics::Metrics;
+use crate::state::{AppState, BroadcastEvent};
+
+/// Проверяем URL на старте, подключение - уже в задаче с переподключением
+pub fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(url) = &state.config.redis_url else {
+        return Ok(());
+    };
+    let client = redis::Client::open(url.as_str()).context("Invalid REDIS_URL")?;
+    info!("📮 Публикация событий в Redis канал {}", state.config.redis_channel);
+    tokio::spawn(run(state.clone(), client));
+    Ok(())
+}
+
+/// Как и GRPC подписка: переподключаемся с растущей задержкой, пока не остановят сервер.
+/// Подписка на канал создается один раз, поэтому за время переподключения события копятся в ней.
+async fn run(state: AppState, client: redis::Client) {
+    let base = Duration::from_millis(state.config.reconnect_base_ms);
+    let max = Duration::from_millis(state.config.reconnect_max_ms);
+    let mut backoff = base;
+    let mut rx = state.tx.subscribe();
+
+    loop {
+        let connected = tokio::select! {
+            result = client.get_multiplexed_async_connection() => result,
+            _ = state.shutdown.cancelled() => return,
+        };
+        match connected {
+            Ok(mut conn) => {
+                info!("✅ Redis подключен");
+                backoff = base;
+                if !publish_all(&state, &mut conn, &mut rx).await {
+                    return;
+                }
+            }
+            Err(e) => error!("Redis ошибка подключения: {}", e),
+        }
+        let delay = crate::jittered(backoff);
+        warn!("Переподключение к Redis через {:?}...", delay);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = state.shutdown.cancelled() => return,
+        }
+        backoff = std::cmp::min(backoff * 2, max);
+    }
+}
+
+/// Публикует события, пока соединение живо. false - пора завершаться (остановка или канал закрыт)
+async fn publish_all(
+    state: &AppState,
+    conn: &mut MultiplexedConnection,
+    rx: &mut broadcast::Receiver<BroadcastEvent>,
+) -> bool {
+    let channel = state.config.redis_channel.as_str();
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = state.shutdown.cancelled() => return false,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Redis не успевает, пропущено {} событий", missed);
+                Metrics::add(&state.metrics.redis_failures, missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return false,
+        };
+        let payload = match crate::json::to_string(&event.create_tx, state.config.json_case) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Не удалось сериализовать событие для Redis: {}", e);
+                Metrics::inc(&state.metrics.redis_failures);
+                continue;
+            }
+        };
+        match conn.publish::<_, _, i64>(channel, payload).await {
+            Ok(receivers) => {
+                debug!("Redis: mint={} получателей={}", event.create_tx.mint_address, receivers);
+                Metrics::inc(&state.metrics.redis_publishes);
+            }
+            Err(e) => {
+                error!("❌ Redis ошибка публикации: {}", e);
+                Metrics::inc(&state.metrics.redis_failures);
+                return true;
+            }
+        }
+    }
+}
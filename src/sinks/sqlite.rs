@@ -0,0 +1,108 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use log::{debug, info, warn};
+use rusqlite::{params, Connection};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::metrics::Metrics;
+use crate::state::AppState;
+use crate::{CreateTransaction, EventType};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS events (
+    signature TEXT PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    source TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    creator TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    received_at INTEGER NOT NULL
+)";
+
+/// Открывает базу на старте (ошибка пути видна сразу) и запускает задачу записи
+pub fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(path) = &state.config.sqlite_path else {
+        return Ok(());
+    };
+    let conn = open(path)?;
+    info!("🗄️ Запись событий в SQLite: {}", path.display());
+    tokio::spawn(run(state.clone(), Arc::new(Mutex::new(conn))));
+    Ok(())
+}
+
+fn open(path: &Path) -> anyhow::Result<Connection> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+    conn.execute(SCHEMA, [])
+        .with_context(|| format!("Failed to create events table in {}", path.display()))?;
+    Ok(conn)
+}
+
+async fn run(state: AppState, conn: Arc<Mutex<Connection>>) {
+    let mut rx = state.tx.subscribe();
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = state.shutdown.cancelled() => return,
+        };
+        match event {
+            // Обновления аккаунтов делят подписи с транзакциями и, как и разрывы GRPC, не являются записями о токенах
+            Ok(event)
+                if matches!(
+                    event.create_tx.event_type,
+                    EventType::Account | EventType::UpstreamReconnect
+                ) => {}
+            Ok(event) => {
+                // rusqlite блокирующий: пишем в пуле blocking потоков, по одному событию,
+                // чтобы сохранить порядок и не держать runtime
+                let conn = conn.clone();
+                let create_tx = event.create_tx;
+                let result = tokio::task::spawn_blocking(move || {
+                    insert(&conn.lock().unwrap(), &create_tx).map(|inserted| (inserted, create_tx))
+                })
+                .await;
+                match result {
+                    Ok(Ok((inserted, create_tx))) => {
+                        if inserted {
+                            debug!("SQLite: записан mint={}", create_tx.mint_address);
+                        }
+                        Metrics::inc(&state.metrics.sqlite_writes);
+                    }
+                    Ok(Err(e)) => {
+                        warn!("❌ SQLite ошибка записи: {}", e);
+                        Metrics::inc(&state.metrics.sqlite_failures);
+                    }
+                    Err(e) => {
+                        warn!("❌ SQLite задача записи упала: {}", e);
+                        Metrics::inc(&state.metrics.sqlite_failures);
+                    }
+                }
+            }
+            Err(RecvError::Lagged(missed)) => {
+                warn!("SQLite не успевает, пропущено {} событий", missed);
+                Metrics::add(&state.metrics.sqlite_failures, missed);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Повторы после переподключения отсекает уникальная подпись; false - строка уже была
+fn insert(conn: &Connection, create_tx: &CreateTransaction) -> rusqlite::Result<bool> {
+    let event_type = create_tx.event_type.as_str();
+    let rows = conn.execute(
+        "INSERT OR IGNORE INTO events (signature, event_type, source, mint, creator, slot, received_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            create_tx.signature,
+            event_type,
+            create_tx.source,
+            create_tx.mint_address,
+            create_tx.creator_address,
+            create_tx.slot as i64,
+            create_tx.received_at as i64,
+        ],
+    )?;
+    Ok(rows > 0)
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use log::{debug, info, warn};
+use tokio::net::UdpSocket;
+
+use crate::metrics::Kind;
+use crate::state::AppState;
+
+/// Безопасный размер UDP пакета без фрагментации; строки метрик собираем в пакеты до этого размера
+const MAX_PACKET_BYTES: usize = 1432;
+
+/// Адрес проверяем на старте, отправка - в задаче по таймеру
+pub async fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(addr) = &state.config.statsd_addr else {
+        return Ok(());
+    };
+    let socket = UdpSocket::bind("0.0.0.0:0").await.context("Failed to bind statsd socket")?;
+    socket
+        .connect(addr.as_str())
+        .await
+        .with_context(|| format!("Invalid statsd address {}", addr))?;
+    info!("📊 Метрики в statsd {} каждые {}ms", addr, state.config.statsd_flush_ms);
+    tokio::spawn(run(state.clone(), socket));
+    Ok(())
+}
+
+async fn run(state: AppState, socket: UdpSocket) {
+    let period = Duration::from_millis(state.config.statsd_flush_ms);
+    let mut flush = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    // statsd суммирует counter сам, поэтому отправляем прирост с прошлой отправки
+    let mut last: HashMap<String, u64> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = flush.tick() => {}
+            _ = state.shutdown.cancelled() => return,
+        }
+        let lines = collect_lines(&state, &mut last);
+        for packet in packets(&lines) {
+            if let Err(e) = socket.send(packet.as_bytes()).await {
+                // UDP без получателя дает ICMP ошибку на следующей отправке: не повод останавливаться
+                warn!("statsd: не удалось отправить метрики: {}", e);
+                break;
+            }
+        }
+        debug!("statsd: отправлено {} метрик", lines.len());
+    }
+}
+
+/// Те же значения, что в /metrics: geyser_creates_detected_total -> <prefix>creates_detected
+fn collect_lines(state: &AppState, last: &mut HashMap<String, u64>) -> Vec<String> {
+    let config = &state.config;
+    let mut lines = Vec::new();
+    let mut counter = |name: String, tags: &str, value: u64| {
+        let previous = last.insert(format!("{}{}", name, tags), value).unwrap_or(0);
+        let delta = value.saturating_sub(previous);
+        if delta > 0 {
+            lines.push(format!("{}:{}|c{}", name, delta, tags));
+        }
+    };
+    let mut gauges = Vec::new();
+    for sample in state.metrics.samples(state.receiver_count()) {
+        let name = statsd_name(&config.statsd_prefix, sample.name);
+        match sample.kind {
+            Kind::Counter => counter(name, "", sample.value),
+            Kind::Gauge => gauges.push(format!("{}:{}|g", name, sample.value)),
+        }
+    }
+    // Метки Prometheus есть только в dogstatsd (теги), обычный statsd такие строки отвергает
+    if config.statsd_tags {
+        for (source, creates, migrations) in state.metrics.source_counts() {
+            let tags = format!("|#source:{}", source);
+            counter(statsd_name(&config.statsd_prefix, "geyser_source_creates_total"), &tags, creates);
+            counter(statsd_name(&config.statsd_prefix, "geyser_source_migrations_total"), &tags, migrations);
+        }
+    }
+    lines.extend(gauges);
+    lines
+}
+
+fn statsd_name(prefix: &str, name: &str) -> String {
+    let name = name.strip_prefix("geyser_").unwrap_or(name);
+    let name = name.strip_suffix("_total").unwrap_or(name);
+    format!("{}{}", prefix, name)
+}
+
+/// Строки через \n, не длиннее MAX_PACKET_BYTES на пакет
+fn packets(lines: &[String]) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut packet = String::new();
+    for line in lines {
+        if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_BYTES {
+            packets.push(std::mem::take(&mut packet));
+        }
+        if !packet.is_empty() {
+            packet.push('\n');
+        }
+        packet.push_str(line);
+    }
+    if !packet.is_empty() {
+        packets.push(packet);
+    }
+    packets
+}
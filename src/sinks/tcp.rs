@@ -0,0 +1,96 @@
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use log::{debug, error, info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::http_server::{is_connection_error, ACCEPT_ERROR_DELAY};
+use crate::state::{AppState, ConnectionGuard};
+
+/// Занимаем порт на старте (ошибка адреса видна сразу) и принимаем подключения в задаче
+pub async fn spawn(state: &AppState) -> anyhow::Result<()> {
+    let Some(addr) = state.config.tcp_listen else {
+        return Ok(());
+    };
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind TCP listener on {}", addr))?;
+    info!("🔌 NDJSON по TCP на {}", addr);
+    tokio::spawn(accept_all(state.clone(), listener));
+    Ok(())
+}
+
+/// Без HTTP и API ключа: рассчитано на локального потребителя, которому важна задержка
+async fn accept_all(state: AppState, listener: TcpListener) {
+    loop {
+        let accepted = tokio::select! {
+            result = listener.accept() => result,
+            _ = state.shutdown.cancelled() => return,
+        };
+        let (socket, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) if is_connection_error(&e) => {
+                debug!("TCP подключение сброшено до accept: {}", e);
+                continue;
+            }
+            Err(e) => {
+                // Как в http_server: без паузы цикл крутится, пока не освободятся дескрипторы
+                error!("TCP ошибка accept: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue;
+            }
+        };
+        let Ok(guard) = ConnectionGuard::acquire(&state, peer.ip()) else {
+            warn!("TCP: {} превысил лимит подключений, закрываем", peer.ip());
+            continue;
+        };
+        tokio::spawn(stream_events(state.clone(), socket, peer, guard));
+    }
+}
+
+/// Как SSE: своя подписка на канал, отставание пропускаем, ошибка записи закрывает только это подключение
+async fn stream_events(state: AppState, mut socket: TcpStream, peer: SocketAddr, _guard: ConnectionGuard) {
+    let _ = socket.set_nodelay(true);
+    let mut rx = state.tx.subscribe();
+    let (mut reader, mut writer) = socket.split();
+    // Входящие данные не ждем, чтение нужно только чтобы сразу заметить закрытие клиентом
+    let mut discard = [0u8; 64];
+    info!("TCP клиент подключен: {}", peer);
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            read = reader.read(&mut discard) => match read {
+                Ok(0) | Err(_) => {
+                    info!("TCP клиент отключился: {}", peer);
+                    return;
+                }
+                Ok(_) => continue,
+            },
+            _ = state.shutdown.cancelled() => break,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("TCP клиент {} не успевает, пропущено {} событий", peer, missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let mut line = match crate::json::to_vec(&event.create_tx, state.config.json_case) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Не удалось сериализовать событие для TCP: {}", e);
+                continue;
+            }
+        };
+        line.push(b'\n');
+        if let Err(e) = writer.write_all(&line).await {
+            debug!("TCP клиент {} отключился: {}", peer, e);
+            return;
+        }
+    }
+    let _ = writer.shutdown().await;
+    info!("TCP клиент отключен: {}", peer);
+}
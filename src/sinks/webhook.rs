@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::metrics::Metrics;
+use crate::state::AppState;
+use crate::CreateTransaction;
+
+/// Начальная пауза между повторами, дальше удваивается
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Запускает по задаче на каждый URL: у каждой своя подписка и своя очередь повторов
+pub fn spawn_all(state: &AppState) -> anyhow::Result<()> {
+    if state.config.webhook_urls.is_empty() {
+        return Ok(());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(state.config.webhook_timeout_ms))
+        .build()?;
+    for url in &state.config.webhook_urls {
+        info!("🪝 Webhook доставка включена: {}", url);
+        tokio::spawn(run(state.clone(), client.clone(), url.clone()));
+    }
+    Ok(())
+}
+
+async fn run(state: AppState, client: reqwest::Client, url: String) {
+    let mut rx = state.tx.subscribe();
+    loop {
+        let event = tokio::select! {
+            result = rx.recv() => result,
+            _ = state.shutdown.cancelled() => return,
+        };
+        match event {
+            Ok(event) => deliver(&state, &client, &url, &event.create_tx).await,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Webhook {} не успевает, пропущено {} событий", url, missed);
+                Metrics::add(&state.metrics.webhook_failures, missed);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn deliver(state: &AppState, client: &reqwest::Client, url: &str, create_tx: &CreateTransaction) {
+    let payload = match crate::json::to_value(create_tx, state.config.json_case) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!("Не удалось сериализовать событие для webhook: {}", e);
+            Metrics::inc(&state.metrics.webhook_failures);
+            return;
+        }
+    };
+    let retries = state.config.webhook_retries;
+    let mut delay = RETRY_BASE_DELAY;
+    for attempt in 0..=retries {
+        let result = client
+            .post(url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => {
+                debug!("Webhook {} доставлен: mint={}", url, create_tx.mint_address);
+                Metrics::inc(&state.metrics.webhook_deliveries);
+                return;
+            }
+            Err(e) if attempt < retries => {
+                debug!("Webhook {} ошибка (попытка {}): {}", url, attempt + 1, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => {
+                warn!(
+                    "❌ Webhook {} не доставлен после {} попыток: mint={} ошибка: {}",
+                    url,
+                    retries + 1,
+                    create_tx.mint_address,
+                    e
+                );
+                Metrics::inc(&state.metrics.webhook_failures);
+            }
+        }
+    }
+}
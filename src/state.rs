@@ -0,0 +1,437 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::Config;
+use crate::creators::CreatorList;
+use crate::dedup::RecentSet;
+use crate::metrics::Metrics;
+use crate::{CreateTransaction, EventType};
+
+/// Самое длинное окно для /stats: более старые отметки Create выбрасываются
+const RATE_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Событие в broadcast канале: Create транзакция с монотонным id для SSE
+#[derive(Debug, Clone)]
+pub struct BroadcastEvent {
+    pub id: u64,
+    pub create_tx: CreateTransaction,
+}
+
+/// Недавние Create/Migrate и счетчики, меняются только вместе
+struct Recent {
+    /// id получают все события, включая служебные
+    next_id: u64,
+    /// Разосланные Create/Migrate (`EventType::is_token_event`)
+    published: u64,
+    capacity: usize,
+    events: VecDeque<BroadcastEvent>,
+}
+
+/// Общее состояние HTTP сервера и GRPC подписки
+pub struct SharedState {
+    pub config: Arc<Config>,
+    pub tx: broadcast::Sender<BroadcastEvent>,
+    /// Каналы /events/:program по source, только с --partition-by-program
+    program_txs: HashMap<String, broadcast::Sender<BroadcastEvent>>,
+    pub metrics: Metrics,
+    /// Последний слот из slot подписки (вершина цепи), 0 пока не получен
+    pub latest_slot: AtomicU64,
+    /// Подписи уже разосланных событий, переживает переподключения GRPC
+    pub seen_signatures: Mutex<RecentSet>,
+    /// Mint уже разосланных событий, только с --mint-dedup-capacity
+    pub seen_mints: Mutex<RecentSet>,
+    /// Отменяется при остановке сервера: завершает GRPC подписку и SSE потоки
+    pub shutdown: CancellationToken,
+    /// Фильтры Create, которые SIGHUP заменяет без переподключения (config::LIVE_SETTINGS)
+    pub live: RwLock<LiveSettings>,
+    recent: Mutex<Recent>,
+    /// Открытые SSE/WebSocket/TCP подключения
+    connections: Mutex<Connections>,
+    /// Время запуска для uptime в /stats
+    pub started_at: Instant,
+    /// Моменты разосланных Create за последний RATE_WINDOW, от старых к новым
+    create_times: Mutex<VecDeque<Instant>>,
+    /// Последний разосланный Create; в отличие от create_times не выбрасывается по RATE_WINDOW
+    last_create: Mutex<Option<Instant>>,
+    /// Create по создателям за --leaderboard-window-secs
+    creators: Mutex<CreatorCounts>,
+}
+
+pub type AppState = Arc<SharedState>;
+
+/// Текущие значения LIVE_SETTINGS; `state.config` остается конфигурацией запуска
+#[derive(Debug, Default)]
+pub struct LiveSettings {
+    pub min_dev_buy_lamports: u64,
+    pub creator_list: Option<CreatorList>,
+}
+
+/// Счетчики для /leaderboard: `counts` всегда совпадает с содержимым `times`
+#[derive(Default)]
+struct CreatorCounts {
+    /// Моменты Create и их создатели, от старых к новым
+    times: VecDeque<(Instant, String)>,
+    counts: HashMap<String, usize>,
+}
+
+impl CreatorCounts {
+    /// Выбрасывает Create старше `window`, создатели без Create в окне удаляются
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while let Some((at, creator)) = self.times.front() {
+            if now.duration_since(*at) <= window {
+                break;
+            }
+            if let Some(count) = self.counts.get_mut(creator) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(creator);
+                }
+            }
+            self.times.pop_front();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Connections {
+    by_ip: HashMap<IpAddr, usize>,
+    total: usize,
+}
+
+/// Какой лимит не дал открыть подключение
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionLimit {
+    /// `max_connections_per_ip`
+    PerIp,
+    /// `max_subscribers`
+    Total,
+}
+
+/// Занятое место в лимитах подключений, освобождается при drop (закрытии соединения)
+pub struct ConnectionGuard {
+    state: AppState,
+    ip: IpAddr,
+}
+
+impl ConnectionGuard {
+    /// Ошибка, если занят общий `max_subscribers` или у IP уже `max_connections_per_ip`
+    /// открытых подключений (0 - без лимита)
+    pub fn acquire(state: &AppState, ip: IpAddr) -> Result<Self, ConnectionLimit> {
+        let config = &state.config;
+        let mut connections = state.connections.lock().unwrap();
+        if config.max_subscribers > 0 && connections.total >= config.max_subscribers {
+            return Err(ConnectionLimit::Total);
+        }
+        let count = connections.by_ip.entry(ip).or_insert(0);
+        if config.max_connections_per_ip > 0 && *count >= config.max_connections_per_ip {
+            return Err(ConnectionLimit::PerIp);
+        }
+        *count += 1;
+        connections.total += 1;
+        Ok(Self {
+            state: state.clone(),
+            ip,
+        })
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.state.connections.lock().unwrap();
+        connections.total -= 1;
+        if let Some(count) = connections.by_ip.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                connections.by_ip.remove(&self.ip);
+            }
+        }
+    }
+}
+
+impl SharedState {
+    pub fn new(config: Arc<Config>, tx: broadcast::Sender<BroadcastEvent>) -> Self {
+        let capacity = config.recent_capacity as usize;
+        let dedup_capacity = config.dedup_capacity;
+        let mint_dedup_capacity = config.mint_dedup_capacity;
+        let metrics = Metrics::default();
+        let mut program_txs = HashMap::new();
+        if let Ok(programs) = config.programs() {
+            metrics.register_sources(programs.iter().map(|program| program.source.as_str()));
+            if config.partition_by_program {
+                for program in &programs {
+                    let (program_tx, _) = broadcast::channel(config.channel_capacity as usize);
+                    program_txs.insert(program.source.clone(), program_tx);
+                }
+            }
+        }
+        Self {
+            config,
+            tx,
+            program_txs,
+            metrics,
+            latest_slot: AtomicU64::new(0),
+            seen_signatures: Mutex::new(RecentSet::new(dedup_capacity)),
+            seen_mints: Mutex::new(RecentSet::new(mint_dedup_capacity)),
+            shutdown: CancellationToken::new(),
+            live: RwLock::new(LiveSettings::default()),
+            recent: Mutex::new(Recent {
+                next_id: 1,
+                published: 0,
+                capacity,
+                events: VecDeque::with_capacity(capacity),
+            }),
+            connections: Mutex::new(Connections::default()),
+            started_at: Instant::now(),
+            create_times: Mutex::new(VecDeque::new()),
+            last_create: Mutex::new(None),
+            creators: Mutex::new(CreatorCounts::default()),
+        }
+    }
+
+    /// Присваивает id, сохраняет Create/Migrate в буфер и рассылает подписчикам.
+    /// Все под одной блокировкой, чтобы `subscribe_from` не пропустил и не задвоил событие.
+    /// Возвращает число получателей в общем канале и канале программы (0, если никто не подписан).
+    pub fn publish(&self, create_tx: CreateTransaction) -> usize {
+        if create_tx.event_type == EventType::Create {
+            let now = Instant::now();
+            let mut times = self.create_times.lock().unwrap();
+            times.push_back(now);
+            prune(&mut times, now);
+            drop(times);
+            *self.last_create.lock().unwrap() = Some(now);
+            let mut creators = self.creators.lock().unwrap();
+            let creator = create_tx.creator_address.clone();
+            *creators.counts.entry(creator.clone()).or_insert(0) += 1;
+            creators.times.push_back((now, creator));
+            creators.prune(now, self.leaderboard_window());
+        }
+        let mut recent = self.recent.lock().unwrap();
+        let event = BroadcastEvent {
+            id: recent.next_id,
+            create_tx,
+        };
+        recent.next_id += 1;
+        // Служебные события не вытесняют Create из буфера, когда upstream часто переподключается
+        if event.create_tx.event_type.is_token_event() {
+            recent.published += 1;
+            if recent.events.len() == recent.capacity {
+                recent.events.pop_front();
+            }
+            recent.events.push_back(event.clone());
+        }
+        // Служебные upstream_reconnect касаются всех программ
+        let program_receivers: usize = if event.create_tx.event_type == EventType::UpstreamReconnect {
+            self.program_txs
+                .values()
+                .map(|program_tx| program_tx.send(event.clone()).unwrap_or(0))
+                .sum()
+        } else {
+            self.program_txs
+                .get(&event.create_tx.source)
+                .map_or(0, |program_tx| program_tx.send(event.clone()).unwrap_or(0))
+        };
+        program_receivers + self.tx.send(event).unwrap_or(0)
+    }
+
+    /// Получатели общего канала и каналов программ (SSE, WebSocket, TCP и sinks)
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count() + self.program_txs.values().map(|tx| tx.receiver_count()).sum::<usize>()
+    }
+
+    /// Открытые SSE/WebSocket/TCP подключения (без внутренних sinks, в отличие от `receiver_count`)
+    pub fn subscriber_count(&self) -> usize {
+        self.connections.lock().unwrap().total
+    }
+
+    /// Время с последнего Create, None - Create еще не было
+    pub fn since_last_create(&self) -> Option<Duration> {
+        self.last_create.lock().unwrap().map(|at| at.elapsed())
+    }
+
+    /// Сколько Create/Migrate разослано с запуска (для --probe), без служебных событий
+    pub fn published_count(&self) -> u64 {
+        self.recent.lock().unwrap().published
+    }
+
+    /// Подписка на живые события плюс буферизованные события новее `last_id`.
+    /// С `program` и --partition-by-program - на канал этой программы; события других
+    /// программ в буфере остаются, их отсеивает фильтр подключения
+    pub fn subscribe_from(
+        &self,
+        last_id: Option<u64>,
+        program: Option<&str>,
+    ) -> (Vec<BroadcastEvent>, broadcast::Receiver<BroadcastEvent>) {
+        let recent = self.recent.lock().unwrap();
+        let rx = program
+            .and_then(|program| self.program_txs.get(program))
+            .unwrap_or(&self.tx)
+            .subscribe();
+        let replay = match last_id {
+            Some(last_id) => recent
+                .events
+                .iter()
+                .filter(|event| event.id > last_id)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        (replay, rx)
+    }
+
+    /// Сколько Create разослано за каждое из окон (не длиннее RATE_WINDOW)
+    pub fn creates_within(&self, windows: &[Duration]) -> Vec<usize> {
+        let now = Instant::now();
+        let mut times = self.create_times.lock().unwrap();
+        prune(&mut times, now);
+        windows
+            .iter()
+            .map(|window| {
+                times
+                    .iter()
+                    .rev()
+                    .take_while(|at| now.duration_since(**at) <= *window)
+                    .count()
+            })
+            .collect()
+    }
+
+    /// До `limit` создателей с наибольшим числом Create за --leaderboard-window-secs,
+    /// при равенстве - по адресу
+    pub fn top_creators(&self, limit: usize) -> Vec<(String, usize)> {
+        let mut creators = self.creators.lock().unwrap();
+        creators.prune(Instant::now(), self.leaderboard_window());
+        let mut top: Vec<_> = creators
+            .counts
+            .iter()
+            .map(|(creator, count)| (creator.clone(), *count))
+            .collect();
+        drop(creators);
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+
+    fn leaderboard_window(&self) -> Duration {
+        Duration::from_secs(self.config.leaderboard_window_secs)
+    }
+
+    /// Последние `limit` Create/Migrate событий, от старых к новым
+    pub fn recent(&self, limit: usize) -> Vec<CreateTransaction> {
+        let recent = self.recent.lock().unwrap();
+        let skip = recent.events.len().saturating_sub(limit);
+        recent
+            .events
+            .iter()
+            .skip(skip)
+            .map(|event| event.create_tx.clone())
+            .collect()
+    }
+}
+
+fn prune(times: &mut VecDeque<Instant>, now: Instant) {
+    while times
+        .front()
+        .is_some_and(|at| now.duration_since(*at) > RATE_WINDOW)
+    {
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn state(args: &[&str]) -> SharedState {
+        let config = Config::parse_from(std::iter::once("solana-geyser-test").chain(args.iter().copied()));
+        let (tx, _) = broadcast::channel(16);
+        SharedState::new(Arc::new(config), tx)
+    }
+
+    fn create(source: &str, creator: &str) -> CreateTransaction {
+        CreateTransaction {
+            event_type: EventType::Create,
+            source: source.to_string(),
+            creator_address: creator.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn service_events_stay_out_of_recent_and_probe_count() {
+        let state = state(&["--recent-capacity", "2"]);
+        state.publish(create("pump_fun", "a"));
+        for _ in 0..3 {
+            state.publish(CreateTransaction {
+                event_type: EventType::UpstreamReconnect,
+                ..Default::default()
+            });
+            state.publish(CreateTransaction {
+                event_type: EventType::Account,
+                ..Default::default()
+            });
+        }
+        state.publish(CreateTransaction {
+            event_type: EventType::Migrate,
+            ..create("pump_fun", "b")
+        });
+
+        assert_eq!(state.published_count(), 2);
+        let recent = state.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].creator_address, "a");
+        assert_eq!(recent[1].event_type, EventType::Migrate);
+        // id идут подряд по всем событиям, повтор отдает только Create/Migrate
+        let (replay, _) = state.subscribe_from(Some(1), None);
+        assert_eq!(replay.iter().map(|event| event.id).collect::<Vec<_>>(), vec![8]);
+    }
+
+    #[test]
+    fn top_creators_orders_limits_and_expires() {
+        let state = state(&["--leaderboard-window-secs", "60"]);
+        for creator in ["b", "a", "c", "a", "b", "a"] {
+            state.publish(create("pump_fun", creator));
+        }
+        state.publish(CreateTransaction {
+            event_type: EventType::Migrate,
+            ..create("pump_fun", "c")
+        });
+
+        let top = |limit| state.top_creators(limit);
+        assert_eq!(top(10), vec![("a".to_string(), 3), ("b".to_string(), 2), ("c".to_string(), 1)]);
+        assert_eq!(top(2).len(), 2);
+        // При равенстве порядок по адресу
+        state.publish(create("pump_fun", "c"));
+        assert_eq!(top(10)[1..], [("b".to_string(), 2), ("c".to_string(), 2)]);
+
+        // Через окно все Create выбывают, счетчики не остаются
+        let later = Instant::now() + Duration::from_secs(61);
+        state.creators.lock().unwrap().prune(later, state.leaderboard_window());
+        assert!(top(10).is_empty());
+        assert!(state.creators.lock().unwrap().times.is_empty());
+    }
+
+    #[test]
+    fn publish_counts_program_channel_receivers() {
+        let state = state(&["--partition-by-program", "--program", "other=11111111111111111111111111111111"]);
+        let (_, mut pump) = state.subscribe_from(None, Some("pump_fun"));
+        let (_, mut other) = state.subscribe_from(None, Some("other"));
+
+        assert_eq!(state.publish(create("pump_fun", "a")), 1);
+        assert_eq!(pump.try_recv().unwrap().create_tx.source, "pump_fun");
+        assert!(other.try_recv().is_err());
+
+        let (_, _all) = state.subscribe_from(None, None);
+        assert_eq!(state.publish(create("other", "a")), 2);
+        assert_eq!(other.try_recv().unwrap().create_tx.source, "other");
+        assert!(pump.try_recv().is_err());
+        assert_eq!(state.receiver_count(), 3);
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::config::Config;
+
+/// OTLP экспорт спанов обработки (tracing). Логи по-прежнему идут через log/env_logger,
+/// а без --otlp-endpoint спаны выключены (`discard_spans`) и ничего не стоят
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    /// None, если --otlp-endpoint не задан
+    pub fn init(config: &Config) -> Result<Option<Self>> {
+        let Some(endpoint) = &config.otlp_endpoint else {
+            discard_spans();
+            return Ok(None);
+        };
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint.clone())
+            .build()
+            .context("Failed to build OTLP span exporter")?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::TraceIdRatioBased(config.otlp_sample_ratio))
+            .with_resource(Resource::builder().with_service_name(config.otlp_service_name.clone()).build())
+            .build();
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::set_global_default(subscriber).context("Failed to install tracing subscriber")?;
+        info!(
+            "🔭 Трассировка OTLP: {} (доля спанов {})",
+            endpoint,
+            config.otlp_sample_ratio
+        );
+        Ok(Some(Self { provider }))
+    }
+
+    /// Отправляет накопленные спаны перед выходом
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            warn!("⚠️ OTLP экспорт не завершился: {}", e);
+        }
+    }
+}
+
+/// Ставит подписчик tracing, который отбрасывает все спаны. Пока глобальный подписчик
+/// не задан, tracing (feature log, включается через axum) пишет спаны в env_logger
+/// строками на каждое обновление
+pub fn discard_spans() {
+    let _ = tracing::subscriber::set_global_default(tracing::subscriber::NoSubscriber::default());
+}